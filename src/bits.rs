@@ -0,0 +1,307 @@
+//! Writes the bits that are set in a bitmap as ranges of bit indices.
+
+use crate::runs::IterRuns;
+use crate::write::{fmt_runs, Steps};
+use crate::{DefaultFormatter, IsAdjacent, Options};
+use core::fmt::{Debug, Formatter};
+
+/// Returns a value that implements `Debug` by writing the indices of the bits that are set in
+/// `words`, collapsing runs of adjacent indices into ranges.
+///
+/// Bit `n` is bit `n % BITS` of `words[n / BITS]`, counting from the least significant bit, unless
+/// [`BitOrder::MsbFirst`] is chosen. Words that have no bits set are skipped with a single test,
+/// and nothing is allocated.
+///
+/// # Example
+/// ```
+/// use dbg_ranges::{debug_bits, BitOrder};
+///
+/// let bitmap = [0xf8u64, u64::MAX];
+/// assert_eq!(format!("{:?}", debug_bits(&bitmap)), "3-7, 64-127");
+///
+/// let bytes = [0b1100_0000u8, 0b0000_0001];
+/// assert_eq!(format!("{:?}", debug_bits(&bytes)), "6-8");
+/// assert_eq!(format!("{:?}", debug_bits(&bytes).order(BitOrder::MsbFirst)), "0-1, 15");
+/// assert_eq!(format!("{:?}", debug_bits(&bytes).clear_bits(true)), "0-5, 9-15");
+/// ```
+pub fn debug_bits<W: BitWord>(words: &[W]) -> DebugBits<'_, W> {
+    DebugBits::new(words)
+}
+
+/// Returns a value that implements `Debug` by writing the indices of the bits that are set in
+/// `mask`, collapsing runs of adjacent indices into ranges.
+///
+/// This is the same as [`debug_bits`] with a single word.
+///
+/// # Example
+/// ```
+/// use dbg_ranges::debug_mask;
+///
+/// assert_eq!(format!("{:?}", debug_mask(&0x8000_00f0u32)), "4-7, 31");
+/// ```
+pub fn debug_mask<W: BitWord>(mask: &W) -> DebugBits<'_, W> {
+    DebugBits::new(core::slice::from_ref(mask))
+}
+
+/// The unsigned integer types that can hold the words of a bitmap.
+pub trait BitWord: Copy + Eq {
+    /// The number of bits in a word
+    const BITS: usize;
+
+    /// The word with no bits set
+    const ZERO: Self;
+
+    /// Returns the index of the lowest bit that is set, counting from the least significant bit.
+    /// The word is not zero.
+    fn lowest_set(self) -> usize;
+
+    /// Returns the word with the lowest bit that is set cleared. The word is not zero.
+    fn clear_lowest(self) -> Self;
+
+    /// Returns the word with every bit inverted.
+    fn invert(self) -> Self;
+
+    /// Returns the word with the order of its bits reversed.
+    fn reverse(self) -> Self;
+}
+
+macro_rules! bit_word {
+    ($t:ty) => {
+        impl BitWord for $t {
+            const BITS: usize = <$t>::BITS as usize;
+
+            const ZERO: Self = 0;
+
+            fn lowest_set(self) -> usize {
+                self.trailing_zeros() as usize
+            }
+
+            fn clear_lowest(self) -> Self {
+                self & (self - 1)
+            }
+
+            fn invert(self) -> Self {
+                !self
+            }
+
+            fn reverse(self) -> Self {
+                self.reverse_bits()
+            }
+        }
+    };
+}
+bit_word!(u8);
+bit_word!(u16);
+bit_word!(u32);
+bit_word!(u64);
+bit_word!(u128);
+bit_word!(usize);
+
+/// The order in which the bits of each word of a bitmap are numbered.
+///
+/// The words themselves are always numbered from the first to the last.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BitOrder {
+    /// Bit 0 is the least significant bit of the first word. This is the layout of the bitmaps
+    /// in most kernels and allocators.
+    #[default]
+    LsbFirst,
+
+    /// Bit 0 is the most significant bit of the first word. This is the layout of bit strings
+    /// that are read from left to right, such as those in some on-disk and network formats.
+    MsbFirst,
+}
+
+/// Displays the indices of the bits that are set in a bitmap, collapsing runs of adjacent indices
+/// into ranges.
+///
+/// Use [`debug_bits`] or [`debug_mask`] to create this type.
+#[derive(Copy, Clone)]
+pub struct DebugBits<'a, W> {
+    /// The words of the bitmap
+    pub words: &'a [W],
+
+    /// Controls how the runs are written
+    pub options: Options<'a>,
+
+    /// The order in which the bits of each word are numbered
+    pub order: BitOrder,
+
+    /// If `true`, the indices of the bits that are clear are written instead
+    pub clear_bits: bool,
+}
+
+impl<'a, W> DebugBits<'a, W> {
+    /// Constructor
+    pub fn new(words: &'a [W]) -> Self {
+        Self {
+            words,
+            options: Options::default(),
+            order: BitOrder::LsbFirst,
+            clear_bits: false,
+        }
+    }
+
+    /// Sets the order in which the bits of each word are numbered. See [`BitOrder`].
+    pub fn order(mut self, order: BitOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Sets whether the indices of the bits that are clear are written, rather than those of the
+    /// bits that are set.
+    pub fn clear_bits(mut self, clear_bits: bool) -> Self {
+        self.clear_bits = clear_bits;
+        self
+    }
+
+    option_setters!('a);
+}
+
+impl<'a, W: BitWord> Debug for DebugBits<'a, W> {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        let bits = SetBits::new(self.words, self.order, self.clear_bits);
+        let runs = IterRuns::new(
+            bits,
+            |a: &usize, b: &usize| IsAdjacent::is_adjacent(a, b),
+            |a: &usize, b: &usize| IsAdjacent::distance(a, b),
+            |a: &usize, b: &usize| IsAdjacent::is_repeat(a, b),
+            &self.options,
+        );
+        fmt_runs(
+            f,
+            runs,
+            &self.options,
+            &DefaultFormatter,
+            Steps::from_trait(),
+            <usize as Debug>::fmt,
+        )
+    }
+}
+
+/// Iterates the indices of the bits that are set in a bitmap.
+#[derive(Clone)]
+pub(crate) struct SetBits<'a, W> {
+    /// The words that have not yet been visited
+    words: core::slice::Iter<'a, W>,
+
+    /// The index of the first bit in `bits`
+    base: usize,
+
+    /// The bits of the current word that have not yet been returned, in least significant bit
+    /// first order
+    bits: W,
+
+    /// The order in which the bits of each word are numbered
+    order: BitOrder,
+
+    /// If `true`, the bits that are clear are returned instead
+    clear_bits: bool,
+}
+
+impl<'a, W: BitWord> SetBits<'a, W> {
+    pub(crate) fn new(words: &'a [W], order: BitOrder, clear_bits: bool) -> Self {
+        Self {
+            words: words.iter(),
+            // The first word is loaded by `next`, which moves `base` on to 0.
+            base: 0usize.wrapping_sub(W::BITS),
+            bits: W::ZERO,
+            order,
+            clear_bits,
+        }
+    }
+
+    /// Returns `word` with the bits that are wanted set, in least significant bit first order.
+    fn load(&self, word: W) -> W {
+        let word = if self.clear_bits { word.invert() } else { word };
+        match self.order {
+            BitOrder::LsbFirst => word,
+            BitOrder::MsbFirst => word.reverse(),
+        }
+    }
+}
+
+impl<'a, W: BitWord> Iterator for SetBits<'a, W> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        // Words without any wanted bits are skipped with a single test.
+        while self.bits == W::ZERO {
+            let word = *self.words.next()?;
+            self.bits = self.load(word);
+            self.base = self.base.wrapping_add(W::BITS);
+        }
+        let bit = self.bits.lowest_set();
+        self.bits = self.bits.clear_lowest();
+        self.base.checked_add(bit)
+    }
+}
+
+impl<'a, W: BitWord> core::iter::FusedIterator for SetBits<'a, W> {}
+
+#[test]
+fn test_debug_bits() {
+    use crate::RangeNotation;
+
+    assert_eq!(format!("{:?}", debug_bits::<u64>(&[])), "");
+    assert_eq!(format!("{:?}", debug_bits(&[0u64; 4])), "");
+    assert_eq!(format!("{:?}", debug_bits(&[0u64, 0, 1 << 5, 0])), "133");
+    assert_eq!(
+        format!("{:?}", debug_bits(&[1u64 << 63, u64::MAX, 1])),
+        "63-128"
+    );
+    assert_eq!(format!("{:?}", debug_bits(&[0x81u8, 0x01])), "0, 7-8");
+    assert_eq!(format!("{:?}", debug_bits(&[0x8000u16, 1])), "15-16");
+    assert_eq!(
+        format!("{:?}", debug_bits(&[0, 3usize])),
+        format!("{}-{}", usize::BITS, usize::BITS + 1)
+    );
+
+    // Single masks
+    assert_eq!(format!("{:?}", debug_mask(&0u32)), "");
+    assert_eq!(format!("{:?}", debug_mask(&u32::MAX)), "0-31");
+    assert_eq!(format!("{:?}", debug_mask(&(1u128 << 127 | 1))), "0, 127");
+    assert_eq!(format!("{:?}", debug_mask(&0b1011u8)), "0-1, 3");
+
+    // Bit order and clear bits
+    let bytes = [0b1000_0011u8, 0xff, 0b0000_0001];
+    assert_eq!(format!("{:?}", debug_bits(&bytes)), "0-1, 7-16");
+    assert_eq!(
+        format!("{:?}", debug_bits(&bytes).order(BitOrder::MsbFirst)),
+        "0, 6-15, 23"
+    );
+    assert_eq!(
+        format!("{:?}", debug_bits(&bytes).clear_bits(true)),
+        "2-6, 17-23"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_bits(&bytes)
+                .order(BitOrder::MsbFirst)
+                .clear_bits(true)
+        ),
+        "1-5, 16-22"
+    );
+    assert_eq!(format!("{:?}", debug_mask(&u64::MAX).clear_bits(true)), "");
+
+    // Options
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_bits(&[0xf8u64, u64::MAX])
+                .notation(RangeNotation::HalfOpen)
+                .counts(true)
+                .header(true)
+        ),
+        "69 items in 2 runs: 3..8 (5), 64..128 (64)"
+    );
+    assert_eq!(
+        format!("{:x?}", debug_mask(&0x0000_ff00_0000_0001u64)),
+        "0, 28-2f"
+    );
+    assert_eq!(
+        format!("{:?}", debug_mask(&0b1011_0111u8).max_gap(1)),
+        "0-7 (missing 3, 6)"
+    );
+}
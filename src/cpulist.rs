@@ -0,0 +1,482 @@
+//! Writes and parses the Linux `cpulist` format.
+//!
+//! This is the format of `/sys/devices/system/cpu/online`, `taskset -c` and `isolcpus=`, which
+//! the kernel calls a bitmap list: runs are written as `start-end`, separated by commas with no
+//! spaces, e.g. `0-3,8-11,16`. The parser accepts everything that the kernel's
+//! `bitmap_parselist` accepts, including strided regions such as `0-15:2/4`, which means the
+//! first 2 of every 4 values from 0 to 15.
+
+use crate::bits::SetBits;
+use crate::runs::IterRuns;
+use crate::write::{fmt_runs, Steps};
+use crate::{
+    fmt_slice, BitOrder, IsAdjacent, NegativeStyle, Options, ParseError, ParseErrorKind, Pretty,
+    RunFormatter,
+};
+use core::fmt::{Display, Formatter};
+use core::ops::RangeInclusive;
+
+/// Returns a value that implements `Display` by writing `items` in the `cpulist` format.
+///
+/// The items are usually sorted CPU numbers, but any list of integers can be written.
+///
+/// # Example
+/// ```
+/// use dbg_ranges::cpulist;
+///
+/// let cpus = [0u32, 1, 2, 3, 8, 9, 10, 11, 16];
+/// assert_eq!(cpulist(&cpus).to_string(), "0-3,8-11,16");
+/// ```
+pub fn cpulist<T: Display + IsAdjacent>(items: &[T]) -> Cpulist<'_, T> {
+    Cpulist::new(items)
+}
+
+/// Returns a value that implements `Display` by writing the bits that are set in `mask` in the
+/// `cpulist` format.
+///
+/// Bit `n` is bit `n % 64` of `mask[n / 64]`, which is the layout of a kernel `cpumask` and of
+/// `cpu_set_t` on 64-bit targets.
+///
+/// # Example
+/// ```
+/// use dbg_ranges::cpulist_mask;
+///
+/// let mask = [0x0f0f, 1 << 0];
+/// assert_eq!(cpulist_mask(&mask).to_string(), "0-3,8-11,64");
+/// ```
+pub fn cpulist_mask(mask: &[u64]) -> CpulistMask<'_> {
+    CpulistMask::new(mask)
+}
+
+/// Returns an iterator over the ranges of values described by `text`, in the `cpulist` format.
+///
+/// A strided region returns one range for each group. Parsing stops at the first error, which is
+/// returned as the last item of the iterator. Use [`ParseCpulist::nbits`] to accept `N` and `all`,
+/// as the kernel does.
+///
+/// # Example
+/// ```
+/// use dbg_ranges::parse_cpulist;
+///
+/// let ranges: Result<Vec<_>, _> = parse_cpulist("0-3,8-15:2/4,16\n").collect();
+/// assert_eq!(ranges.unwrap(), [0..=3, 8..=9, 12..=13, 16..=16]);
+/// ```
+pub fn parse_cpulist(text: &str) -> ParseCpulist<'_> {
+    ParseCpulist::new(text)
+}
+
+/// Parses `text`, in the `cpulist` format, into a bitmask, with the same layout as
+/// [`cpulist_mask`].
+///
+/// `mask` is cleared before the bits are set. The size of `mask` is the number of bits, as for
+/// [`ParseCpulist::nbits`], so a value that does not fit is an error. If there is an error, then
+/// `mask` is not changed.
+///
+/// # Example
+/// ```
+/// use dbg_ranges::{cpulist_mask, parse_cpulist_mask};
+///
+/// let mut mask = [0u64; 2];
+/// parse_cpulist_mask("0-3,8-11,64", &mut mask).unwrap();
+/// assert_eq!(mask, [0x0f0f, 1]);
+/// assert_eq!(cpulist_mask(&mask).to_string(), "0-3,8-11,64");
+///
+/// parse_cpulist_mask("all:1/32", &mut mask).unwrap();
+/// assert_eq!(cpulist_mask(&mask).to_string(), "0,32,64,96");
+/// ```
+pub fn parse_cpulist_mask(text: &str, mask: &mut [u64]) -> Result<(), ParseError> {
+    let nbits = u32::try_from(mask.len().saturating_mul(64)).unwrap_or(u32::MAX);
+    let ranges = ParseCpulist::new(text).nbits(nbits);
+    for range in ranges.clone() {
+        range?;
+    }
+
+    mask.fill(0);
+    for range in ranges {
+        for bit in range? {
+            mask[bit as usize / 64] |= 1 << (bit % 64);
+        }
+    }
+    Ok(())
+}
+
+/// Returns the options of the `cpulist` format, which is always written on one line, even with
+/// `{:#}`, and with a plain `-` in every range, even if an endpoint is negative.
+fn cpulist_options() -> Options<'static> {
+    Options {
+        pretty: Pretty::Off,
+        negatives: NegativeStyle::Keep,
+        ..Options::default()
+    }
+}
+
+/// Writes the parts of a list in the `cpulist` format.
+struct CpulistFormatter;
+
+impl RunFormatter for CpulistFormatter {
+    fn write_between(&self, f: &mut Formatter, _multiline: bool) -> core::fmt::Result {
+        f.write_str(",")
+    }
+}
+
+/// Displays a list of integers in the Linux `cpulist` format, e.g. `0-3,8-11,16`.
+///
+/// The format is fixed, so this type has no options. Use [`cpulist`] to create this type.
+#[derive(Copy, Clone)]
+pub struct Cpulist<'a, T> {
+    /// The items that will be displayed
+    pub items: &'a [T],
+}
+
+impl<'a, T> Cpulist<'a, T> {
+    /// Constructor
+    pub fn new(items: &'a [T]) -> Self {
+        Self { items }
+    }
+}
+
+impl<'a, T> Display for Cpulist<'a, T>
+where
+    T: Display + IsAdjacent,
+{
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        fmt_slice(
+            f,
+            self.items,
+            &cpulist_options(),
+            &CpulistFormatter,
+            <T as Display>::fmt,
+        )
+    }
+}
+
+/// Displays the bits that are set in a bitmask in the Linux `cpulist` format, e.g.
+/// `0-3,8-11,16`.
+///
+/// The format is fixed, so this type has no options. Use [`cpulist_mask`] to create this type.
+#[derive(Copy, Clone)]
+pub struct CpulistMask<'a> {
+    /// The bitmask, where bit `n` is bit `n % 64` of `mask[n / 64]`
+    pub mask: &'a [u64],
+}
+
+impl<'a> CpulistMask<'a> {
+    /// Constructor
+    pub fn new(mask: &'a [u64]) -> Self {
+        Self { mask }
+    }
+}
+
+impl<'a> Display for CpulistMask<'a> {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        let options = cpulist_options();
+        let runs = IterRuns::new(
+            SetBits::new(self.mask, BitOrder::LsbFirst, false),
+            |a: &usize, b: &usize| IsAdjacent::is_adjacent(a, b),
+            |a: &usize, b: &usize| IsAdjacent::distance(a, b),
+            |a: &usize, b: &usize| IsAdjacent::is_repeat(a, b),
+            &options,
+        );
+        fmt_runs(
+            f,
+            runs,
+            &options,
+            &CpulistFormatter,
+            Steps::from_trait(),
+            <usize as Display>::fmt,
+        )
+    }
+}
+
+/// Iterates the ranges described by a string in the `cpulist` format. Use [`parse_cpulist`] to
+/// create this type.
+#[derive(Clone)]
+pub struct ParseCpulist<'a> {
+    /// The input
+    text: &'a str,
+
+    /// The byte offset of the next character to parse
+    pos: usize,
+
+    /// The number of bits, or `None` if it is not known
+    nbits: Option<u32>,
+
+    /// The strided region that is being returned one group at a time
+    region: Option<Region>,
+
+    /// `true` once the input has been used up, or an error has been returned
+    done: bool,
+}
+
+/// A region of a `cpulist`, which is `start-end:used/group`: the first `used` values of every
+/// `group` values from `start` to `end`.
+#[derive(Copy, Clone)]
+struct Region {
+    start: u32,
+    end: u32,
+    used: u32,
+    group: u32,
+}
+
+impl<'a> ParseCpulist<'a> {
+    /// Constructor
+    pub fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            pos: 0,
+            nbits: None,
+            region: None,
+            done: false,
+        }
+    }
+
+    /// Sets the number of bits in the bitmap that the list describes. This allows `N`, which is
+    /// the last bit, and `all`, which is every bit, and makes a value that does not fit an
+    /// error, as in the kernel.
+    pub fn nbits(mut self, nbits: u32) -> Self {
+        self.nbits = Some(nbits);
+        self
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            kind,
+            pos: self.pos,
+        }
+    }
+
+    /// Consumes `s` if the input continues with it.
+    fn eat(&mut self, s: &str) -> bool {
+        let found = self.rest().starts_with(s);
+        if found {
+            self.pos += s.len();
+        }
+        found
+    }
+
+    /// Returns `true` if the input continues with the end of a region, which is a separator or
+    /// the end of the input.
+    fn at_region_end(&self) -> bool {
+        let rest = self.rest();
+        rest.is_empty() || rest.starts_with(is_separator)
+    }
+
+    /// Parses a decimal number, or `N` for the last bit.
+    fn parse_num(&mut self) -> Result<u32, ParseError> {
+        let start = self.pos;
+        let out_of_range = ParseError {
+            kind: ParseErrorKind::OutOfRange,
+            pos: start,
+        };
+        if let Some(nbits) = self.nbits {
+            if self.eat("N") {
+                return nbits.checked_sub(1).ok_or(out_of_range);
+            }
+        }
+
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error(ParseErrorKind::ExpectedItem));
+        }
+        self.pos += len;
+        rest[..len].parse().map_err(|_| out_of_range)
+    }
+
+    fn parse_region(&mut self) -> Result<Region, ParseError> {
+        let region_pos = self.pos;
+        let nbits = self.nbits;
+        let (start, end) = match nbits {
+            Some(nbits) if self.eat("all") => {
+                let last = nbits.checked_sub(1).ok_or(ParseError {
+                    kind: ParseErrorKind::OutOfRange,
+                    pos: region_pos,
+                })?;
+                (0, last)
+            }
+            _ => {
+                let start = self.parse_num()?;
+                if self.at_region_end() {
+                    (start, start)
+                } else if self.eat("-") {
+                    (start, self.parse_num()?)
+                } else {
+                    return Err(self.error(ParseErrorKind::ExpectedComma));
+                }
+            }
+        };
+
+        // A region without a group uses every value, in a single group.
+        let (used, group) = if self.at_region_end() {
+            (u32::MAX, u32::MAX)
+        } else if self.eat(":") {
+            let used = self.parse_num()?;
+            if !self.eat("/") {
+                return Err(self.error(ParseErrorKind::ExpectedSlash));
+            }
+            let group = self.parse_num()?;
+            if !self.at_region_end() {
+                return Err(self.error(ParseErrorKind::ExpectedComma));
+            }
+            (used, group)
+        } else {
+            return Err(self.error(ParseErrorKind::ExpectedComma));
+        };
+
+        let error = |kind| ParseError {
+            kind,
+            pos: region_pos,
+        };
+        if start > end {
+            return Err(error(ParseErrorKind::ReversedRange));
+        }
+        if group == 0 || used > group {
+            return Err(error(ParseErrorKind::InvalidGroup));
+        }
+        if nbits.is_some_and(|nbits| end >= nbits) {
+            return Err(error(ParseErrorKind::OutOfRange));
+        }
+
+        Ok(Region {
+            start,
+            end,
+            used,
+            group,
+        })
+    }
+
+    fn parse_next(&mut self) -> Result<Option<RangeInclusive<u32>>, ParseError> {
+        loop {
+            if let Some(region) = &mut self.region {
+                let start = region.start;
+                let last = start.saturating_add(region.used - 1).min(region.end);
+                match start.checked_add(region.group) {
+                    Some(next) if next <= region.end => region.start = next,
+                    _ => self.region = None,
+                }
+                return Ok(Some(start..=last));
+            }
+
+            // Regions are separated by any number of commas and spaces, and the input ends at the
+            // first newline, as in the kernel.
+            let rest = self.rest();
+            let rest = rest.trim_start_matches(|c: char| c != '\n' && is_separator(c));
+            self.pos = self.text.len() - rest.len();
+            if rest.is_empty() {
+                return Ok(None);
+            }
+            if rest.starts_with('\n') {
+                self.pos = self.text.len();
+                return Ok(None);
+            }
+
+            let region = self.parse_region()?;
+            if region.used != 0 {
+                self.region = Some(region);
+            }
+        }
+    }
+}
+
+/// Returns `true` for the characters that end a region, which are commas and whitespace,
+/// including the newline that ends the input.
+fn is_separator(c: char) -> bool {
+    c == ',' || c.is_ascii_whitespace()
+}
+
+impl<'a> Iterator for ParseCpulist<'a> {
+    type Item = Result<RangeInclusive<u32>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let result = self.parse_next();
+        if !matches!(result, Ok(Some(_))) {
+            self.done = true;
+        }
+        result.transpose()
+    }
+}
+
+impl<'a> core::iter::FusedIterator for ParseCpulist<'a> {}
+
+#[test]
+fn test_cpulist() {
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    assert_eq!(cpulist::<u32>(&[]).to_string(), "");
+    assert_eq!(cpulist(&[5u16]).to_string(), "5");
+    assert_eq!(cpulist(&[0u8, 1, 3, 4, 5, 7]).to_string(), "0-1,3-5,7");
+    assert_eq!(format!("{:>12}|", cpulist(&[0u8, 1, 3])), "       0-1,3|");
+    assert_eq!(format!("{:#}", cpulist(&[0u32, 1, 2, 5])), "0-2,5");
+    assert_eq!(cpulist(&[-3i32, -2, -1, 4]).to_string(), "-3--1,4");
+
+    assert_eq!(cpulist_mask(&[]).to_string(), "");
+    assert_eq!(cpulist_mask(&[0, 0]).to_string(), "");
+    assert_eq!(cpulist_mask(&[u64::MAX]).to_string(), "0-63");
+    assert_eq!(cpulist_mask(&[1 << 63, 1, 0, 2]).to_string(), "63-64,193");
+    assert_eq!(format!("{:#}", cpulist_mask(&[0b10111])), "0-2,4");
+
+    let parse = |text| parse_cpulist(text).collect::<Result<Vec<_>, _>>();
+    assert_eq!(parse(""), Ok(Vec::new()));
+    assert_eq!(parse("\n"), Ok(Vec::new()));
+    assert_eq!(parse("0-3,8-11,16\n"), Ok(vec![0..=3, 8..=11, 16..=16]));
+    assert_eq!(parse(" 1,,2 3\nx"), Ok(vec![1..=1, 2..=2, 3..=3]));
+    assert_eq!(parse("0-15:2/4"), Ok(vec![0..=1, 4..=5, 8..=9, 12..=13]));
+    assert_eq!(parse("0-14:3/4"), Ok(vec![0..=2, 4..=6, 8..=10, 12..=14]));
+    assert_eq!(parse("2-5:0/3,7"), Ok(vec![7..=7]));
+    assert_eq!(parse("4-4:1/1"), Ok(vec![4..=4]));
+    assert_eq!(
+        parse("4294967294-4294967295:1/1"),
+        Ok(vec![4294967294..=4294967294, 4294967295..=4294967295])
+    );
+
+    let all: Result<Vec<_>, _> = parse_cpulist("all,N,0-N:1/3").nbits(8).collect();
+    assert_eq!(all, Ok(vec![0..=7, 7..=7, 0..=0, 3..=3, 6..=6]));
+
+    let error = |text, nbits: Option<u32>| {
+        let mut ranges = parse_cpulist(text);
+        if let Some(nbits) = nbits {
+            ranges = ranges.nbits(nbits);
+        }
+        let error = ranges.find_map(Result::err).expect("expected an error");
+        (error.kind, error.pos)
+    };
+    assert_eq!(error("1-", None), (ParseErrorKind::ExpectedItem, 2));
+    assert_eq!(error("1;2", None), (ParseErrorKind::ExpectedComma, 1));
+    assert_eq!(error("N", None), (ParseErrorKind::ExpectedItem, 0));
+    assert_eq!(error("all", None), (ParseErrorKind::ExpectedItem, 0));
+    assert_eq!(error("0-3:1", None), (ParseErrorKind::ExpectedSlash, 5));
+    assert_eq!(error("0-3:1/2x", None), (ParseErrorKind::ExpectedComma, 7));
+    assert_eq!(error("1, 5-3", None), (ParseErrorKind::ReversedRange, 3));
+    assert_eq!(error("0-3:3/2", None), (ParseErrorKind::InvalidGroup, 0));
+    assert_eq!(error("0-3:0/0", None), (ParseErrorKind::InvalidGroup, 0));
+    assert_eq!(error("4294967296", None), (ParseErrorKind::OutOfRange, 0));
+    assert_eq!(error("0,8", Some(8)), (ParseErrorKind::OutOfRange, 2));
+    assert_eq!(error("N", Some(0)), (ParseErrorKind::OutOfRange, 0));
+    assert_eq!(error("all", Some(0)), (ParseErrorKind::OutOfRange, 0));
+
+    // Round trips through a bitmask.
+    let mut mask = [u64::MAX; 3];
+    assert_eq!(parse_cpulist_mask("", &mut mask), Ok(()));
+    assert_eq!(mask, [0; 3]);
+    assert_eq!(parse_cpulist_mask("1,5-130:2/64,191", &mut mask), Ok(()));
+    assert_eq!(mask, [0b110_0010, 0b110_0000, 1 << 63]);
+    assert_eq!(cpulist_mask(&mask).to_string(), "1,5-6,69-70,191");
+    assert!(parse_cpulist_mask("0,192", &mut mask).is_err());
+    assert_eq!(mask, [0b110_0010, 0b110_0000, 1 << 63]);
+
+    let cpus: Vec<u32> = (0..200).filter(|n| n % 7 != 3).collect();
+    let text = cpulist(&cpus).to_string();
+    let parsed: Vec<u32> = parse_cpulist(&text).flat_map(Result::unwrap).collect();
+    assert_eq!(parsed, cpus);
+}
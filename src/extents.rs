@@ -0,0 +1,401 @@
+//! Writes mappings from logical to physical positions, such as the blocks of a file, as extents.
+
+use crate::runs::Span;
+use crate::write::{fmt_prefixed_range, fmt_runs, Steps};
+use crate::{DefaultFormatter, IsAdjacent, Options};
+use core::borrow::Borrow;
+use core::fmt::{Debug, Formatter};
+use core::marker::PhantomData;
+
+/// Returns a value that implements `Debug` by writing a mapping from logical to physical
+/// positions as extents, such as `L0-L99 -> P5000-P5099, L100 -> P42`.
+///
+/// Consecutive entries are collapsed into an extent if both their logical and their physical
+/// positions are adjacent, as defined by the `IsAdjacent` trait. Logical positions that are
+/// skipped between two entries are written as a hole, such as `L101-L104 -> hole`.
+///
+/// # Example
+/// ```
+/// use dbg_ranges::debug_extents;
+///
+/// let mapping = [(0u64, 5000u64), (1, 5001), (2, 5002), (3, 42), (6, 7000), (7, 7001)];
+/// assert_eq!(
+///     format!("{:?}", debug_extents(&mapping)),
+///     "L0-L2 -> P5000-P5002, L3 -> P42, L4-L5 -> hole, L6-L7 -> P7000-P7001"
+/// );
+/// ```
+pub fn debug_extents<L, P>(mapping: &[(L, P)]) -> DebugExtents<'_, L, P>
+where
+    L: Debug + IsAdjacent,
+    P: Debug + IsAdjacent,
+{
+    DebugExtents::new(mapping)
+}
+
+/// Returns a value that implements `Debug` by writing a list of physical positions, indexed by
+/// logical position, as extents, such as `L0-L99 -> P5000-P5099, L100 -> P42`.
+///
+/// This is the same as [`debug_extents`] where the logical position of each item is its index,
+/// so there are no holes.
+///
+/// # Example
+/// ```
+/// use dbg_ranges::debug_extents_indexed;
+///
+/// let blocks = [5000u64, 5001, 5002, 42, 7000, 7001];
+/// assert_eq!(
+///     format!("{:?}", debug_extents_indexed(&blocks)),
+///     "L0-L2 -> P5000-P5002, L3 -> P42, L4-L5 -> P7000-P7001"
+/// );
+/// ```
+pub fn debug_extents_indexed<P>(physical: &[P]) -> DebugExtentsIndexed<'_, P>
+where
+    P: Debug + IsAdjacent,
+{
+    DebugExtentsIndexed::new(physical)
+}
+
+/// Displays a mapping from logical to physical positions as extents.
+///
+/// Use [`debug_extents`] to create this type.
+#[derive(Copy, Clone)]
+pub struct DebugExtents<'a, L, P> {
+    /// The logical and physical position of each entry, in increasing logical order
+    pub mapping: &'a [(L, P)],
+
+    /// Controls how the extents are written. Each extent is a run, and the entries in it are its
+    /// items. Holes are written between the runs, but are not counted as runs. The extents are
+    /// found by comparing both positions, so only the options that apply to the whole list and
+    /// to the way ranges are written are used.
+    pub options: Options<'a>,
+}
+
+impl<'a, L, P> DebugExtents<'a, L, P> {
+    /// Constructor
+    pub fn new(mapping: &'a [(L, P)]) -> Self {
+        Self {
+            mapping,
+            options: Options::default(),
+        }
+    }
+
+    option_setters!(list 'a);
+    option_setters!(range 'a);
+}
+
+impl<'a, L, P> Debug for DebugExtents<'a, L, P>
+where
+    L: Debug + IsAdjacent,
+    P: Debug + IsAdjacent,
+{
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        let entries = self
+            .mapping
+            .iter()
+            .map(|(logical, physical)| (logical, physical));
+        fmt_extents::<_, _, _, L, P>(f, entries, &self.options)
+    }
+}
+
+/// Displays a list of physical positions, indexed by logical position, as extents.
+///
+/// Use [`debug_extents_indexed`] to create this type.
+#[derive(Copy, Clone)]
+pub struct DebugExtentsIndexed<'a, P> {
+    /// The physical position of each logical position
+    pub physical: &'a [P],
+
+    /// Controls how the extents are written. Each extent is a run, and the entries in it are
+    /// its items. As with [`DebugExtents`], only the options that apply to the whole list and to
+    /// the way ranges are written are used.
+    pub options: Options<'a>,
+}
+
+impl<'a, P> DebugExtentsIndexed<'a, P> {
+    /// Constructor
+    pub fn new(physical: &'a [P]) -> Self {
+        Self {
+            physical,
+            options: Options::default(),
+        }
+    }
+
+    option_setters!(list 'a);
+    option_setters!(range 'a);
+}
+
+impl<'a, P> Debug for DebugExtentsIndexed<'a, P>
+where
+    P: Debug + IsAdjacent,
+{
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        let entries = self.physical.iter().enumerate();
+        fmt_extents::<_, _, _, usize, P>(f, entries, &self.options)
+    }
+}
+
+/// Writes the extents and holes in a list of logical and physical positions.
+fn fmt_extents<I, LQ, PQ, L, P>(
+    f: &mut Formatter,
+    entries: I,
+    options: &Options,
+) -> core::fmt::Result
+where
+    I: Iterator<Item = (LQ, PQ)> + Clone,
+    LQ: Borrow<L> + Clone,
+    PQ: Borrow<P> + Clone,
+    L: Debug + IsAdjacent,
+    P: Debug + IsAdjacent,
+{
+    let runs = Extents::<_, _, _, L, P> {
+        entries,
+        pending: None,
+        prev_last: None,
+        _positions: PhantomData,
+    };
+    fmt_runs(
+        f,
+        runs,
+        options,
+        &DefaultFormatter,
+        Steps::none(),
+        |extent: &Extent<LQ, PQ, L>, f: &mut Formatter| match extent {
+            Extent::Mapped { first, last, len } => {
+                let last = last.as_ref();
+                fmt_prefixed_range(
+                    f,
+                    "L",
+                    first.0.borrow(),
+                    last.map(|l| l.0.borrow()),
+                    *len,
+                    options,
+                )?;
+                f.write_str(" -> ")?;
+                fmt_prefixed_range(
+                    f,
+                    "P",
+                    first.1.borrow(),
+                    last.map(|l| l.1.borrow()),
+                    *len,
+                    options,
+                )
+            }
+            Extent::Hole { first, last, len } => {
+                fmt_prefixed_range(f, "L", first, last.as_ref(), *len, options)?;
+                f.write_str(" -> hole")
+            }
+        },
+    )
+}
+
+/// An extent or a hole, found by [`Extents`].
+enum Extent<LQ, PQ, L> {
+    /// Entries whose logical and physical positions both advance together
+    Mapped {
+        first: (LQ, PQ),
+        last: Option<(LQ, PQ)>,
+        len: u128,
+    },
+
+    /// Logical positions that are skipped between two extents
+    Hole {
+        first: L,
+        last: Option<L>,
+        len: u128,
+    },
+}
+
+/// Iterates the extents and holes in a list of logical and physical positions. Each extent is
+/// returned as a run of one item, whose length is the number of entries in it. Each hole is
+/// returned as a span of length 0, so that it is written between the runs without being one.
+struct Extents<I, LQ, PQ, L, P> {
+    /// The entries that have not yet been visited
+    entries: I,
+
+    /// The first entry of the next extent, if it was read while finding a hole
+    pending: Option<(LQ, PQ)>,
+
+    /// The last logical position of the previous extent
+    prev_last: Option<LQ>,
+
+    _positions: PhantomData<fn() -> (L, P)>,
+}
+
+impl<I: Clone, LQ: Clone, PQ: Clone, L, P> Clone for Extents<I, LQ, PQ, L, P> {
+    fn clone(&self) -> Self {
+        Self {
+            entries: self.entries.clone(),
+            pending: self.pending.clone(),
+            prev_last: self.prev_last.clone(),
+            _positions: PhantomData,
+        }
+    }
+}
+
+impl<I, LQ, PQ, L, P> Iterator for Extents<I, LQ, PQ, L, P>
+where
+    I: Iterator<Item = (LQ, PQ)> + Clone,
+    LQ: Borrow<L> + Clone,
+    PQ: Borrow<P> + Clone,
+    L: IsAdjacent,
+    P: IsAdjacent,
+{
+    type Item = Span<Extent<LQ, PQ, L>, core::iter::Empty<Extent<LQ, PQ, L>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let first = match self.pending.take() {
+            Some(first) => first,
+            None => self.entries.next()?,
+        };
+
+        let extent = match self
+            .prev_last
+            .take()
+            .and_then(|prev| hole(prev.borrow(), first.0.borrow()))
+        {
+            Some(hole) => {
+                self.pending = Some(first);
+                hole
+            }
+            None => {
+                let mut last: Option<(LQ, PQ)> = None;
+                let mut len: u128 = 1;
+                while let Some(next) = self.entries.clone().next() {
+                    let prev = last.as_ref().unwrap_or(&first);
+                    if !(prev.0.borrow().is_adjacent(next.0.borrow())
+                        && prev.1.borrow().is_adjacent(next.1.borrow()))
+                    {
+                        break;
+                    }
+                    self.entries.next();
+                    last = Some(next);
+                    len += 1;
+                }
+                self.prev_last = Some(last.as_ref().unwrap_or(&first).0.clone());
+                Extent::Mapped { first, last, len }
+            }
+        };
+
+        let len = match extent {
+            Extent::Mapped { len, .. } => len,
+            Extent::Hole { .. } => 0,
+        };
+        Some(Span {
+            first: extent,
+            last: None,
+            len,
+            descending: false,
+            step: None,
+            missing: 0,
+            items: core::iter::empty(),
+        })
+    }
+}
+
+/// Returns the hole between the logical positions `prev` and `next`, if `next` is more than one
+/// position after `prev`, and the positions in between can be computed.
+fn hole<L, PQ, LQ>(prev: &L, next: &L) -> Option<Extent<LQ, PQ, L>>
+where
+    L: IsAdjacent,
+{
+    let distance = prev.distance(next)?;
+    if distance < 2 {
+        return None;
+    }
+    let first = prev.successor()?;
+    let last = if distance == 2 {
+        None
+    } else {
+        Some(next.predecessor()?)
+    };
+    Some(Extent::Hole {
+        first,
+        last,
+        len: distance - 1,
+    })
+}
+
+#[test]
+fn test_debug_extents() {
+    use crate::{NegativeStyle, RangeNotation};
+
+    let empty: [(u32, u64); 0] = [];
+    assert_eq!(format!("{:?}", debug_extents(&empty)), "");
+    assert_eq!(format!("{:?}", debug_extents(&[(7u32, 9u64)])), "L7 -> P9");
+
+    // An extent ends when either position stops advancing.
+    let mapping: [(u32, u64); 7] = [
+        (0, 10),
+        (1, 11),
+        (2, 13),
+        (3, 14),
+        (3, 15),
+        (10, 16),
+        (12, 20),
+    ];
+    assert_eq!(
+        format!("{:?}", debug_extents(&mapping)),
+        "L0-L1 -> P10-P11, L2-L3 -> P13-P14, L3 -> P15, L4-L9 -> hole, L10 -> P16, L11 -> hole, \
+         L12 -> P20"
+    );
+
+    // Descending physical positions are not an extent.
+    assert_eq!(
+        format!("{:?}", debug_extents_indexed(&[5u8, 4, 3])),
+        "L0 -> P5, L1 -> P4, L2 -> P3"
+    );
+    assert_eq!(
+        format!("{:?}", debug_extents_indexed(&['a', 'b', 'x'])),
+        "L0-L1 -> P'a'-P'b', L2 -> P'x'"
+    );
+
+    // Options apply to both sides. Holes are written between the extents, but are not runs.
+    let mapping = [
+        (0u64, 0x1000u64),
+        (1, 0x1001),
+        (4, 0x2000),
+        (5, 0x2001),
+        (6, 0x2002),
+    ];
+    assert_eq!(
+        format!(
+            "{:x?}",
+            debug_extents(&mapping).notation(RangeNotation::HalfOpen)
+        ),
+        "L0..L2 -> P1000..P1002, L2..L4 -> hole, L4..L7 -> P2000..P2003"
+    );
+    assert_eq!(
+        format!("{:?}", debug_extents(&mapping).header(true).max_runs(2)),
+        "5 items in 2 runs: L0-L1 -> P4096-P4097, L2-L3 -> hole, L4-L6 -> P8192-P8194"
+    );
+    assert_eq!(
+        format!("{:?}", debug_extents(&mapping).header(true).max_runs(1)),
+        "5 items in 2 runs: L0-L1 -> P4096-P4097, ... 1 more run (3 items) ..."
+    );
+    assert_eq!(
+        format!("{:?}", debug_extents(&mapping).stats(true)),
+        "L0-L1 -> P4096-P4097, L2-L3 -> hole, L4-L6 -> P8192-P8194; RunStats { items: 5, runs: 2, \
+         longest: 3, shortest: 2, mean_len: 2.5, largest_gap: None, fragmentation: 0.4 }"
+    );
+
+    // Holes next to a run that is left out are left out with it.
+    let sparse = [(0u32, 0u32), (2, 10), (4, 20)];
+    assert_eq!(
+        format!("{:?}", debug_extents(&sparse).header(true).max_runs(2)),
+        "3 items in 3 runs: L0 -> P0, ... 1 more run (1 item) ..., L4 -> P20"
+    );
+
+    // The sign of a position is checked before its prefix is added.
+    let negative = [(-3i32, 0u32), (-2, 1), (0, 7), (1, 8)];
+    assert_eq!(
+        format!("{:?}", debug_extents(&negative)),
+        "L-3..=L-2 -> P0-P1, L-1 -> hole, L0-L1 -> P7-P8"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_extents(&negative).negatives(NegativeStyle::Parenthesize)
+        ),
+        "(L-3)-(L-2) -> P0-P1, L-1 -> hole, L0-L1 -> P7-P8"
+    );
+}
@@ -0,0 +1,173 @@
+//! The trait that writes the parts of a list of runs.
+
+use crate::write::{fmt_range, is_negative};
+use crate::Options;
+use core::fmt::{Debug, Formatter};
+
+/// Writes the parts of a list of runs: single items, repeated items, ranges, the separators
+/// between runs, and the text before and after the list.
+///
+/// Every method has a default implementation, which is the output of [`DefaultFormatter`], so an
+/// implementation only needs to override the parts that it changes. Items are passed as
+/// `&dyn Debug` values that write the item in the way chosen by the wrapper type, which may be
+/// its `Debug` or `Display` implementation or a custom function. Write them with `item.fmt(f)`
+/// so that the caller's formatting flags, such as `{:x?}`, are applied.
+///
+/// The counts, the header and the elision marker are still controlled by [`Options`]. When
+/// formatting with `{:#?}`, the multi-line layout chosen by [`Options::pretty`] passes `true` as
+/// the `multiline` argument of [`write_open`](Self::write_open),
+/// [`write_between`](Self::write_between) and [`write_close`](Self::write_close). In that layout
+/// the list writes the line breaks and indentation itself, and the separator follows every run,
+/// including the last.
+///
+/// # Example
+/// ```
+/// use core::fmt::{Debug, Formatter, Result};
+/// use dbg_ranges::{debug_adjacent, Options, RunFormatter, RunRange};
+///
+/// struct Tabs;
+///
+/// impl RunFormatter for Tabs {
+///     fn write_range(&self, f: &mut Formatter, range: &RunRange, _: &Options) -> Result {
+///         f.write_str("[")?;
+///         range.first.fmt(f)?;
+///         f.write_str("..")?;
+///         range.last.fmt(f)?;
+///         f.write_str("]")
+///     }
+///
+///     fn write_between(&self, f: &mut Formatter, multiline: bool) -> Result {
+///         f.write_str(if multiline { ";" } else { "\t" })
+///     }
+/// }
+///
+/// let items = [1u32, 2, 3, 7];
+/// assert_eq!(format!("{:?}", debug_adjacent(&items).formatter(Tabs)), "[1..3]\t7");
+/// assert_eq!(
+///     format!("{:#?}", debug_adjacent(&items).formatter(Tabs)),
+///     "[\n    [1..3];\n    7;\n]"
+/// );
+/// ```
+pub trait RunFormatter {
+    /// Writes a run that is written as a single item.
+    fn write_single(&self, f: &mut Formatter, item: &dyn Debug) -> core::fmt::Result {
+        item.fmt(f)
+    }
+
+    /// Writes a run of `count` repeated items, which are all written as `item`. The default
+    /// writes `item` followed by the count, e.g. `0 x4`.
+    fn write_repeat(&self, f: &mut Formatter, item: &dyn Debug, count: usize) -> core::fmt::Result {
+        item.fmt(f)?;
+        write!(f, " x{}", count)
+    }
+
+    /// Writes a run of two or more items as a range.
+    ///
+    /// The default implementation uses [`Options::notation`], [`Options::sep`] and
+    /// [`Options::negatives`].
+    fn write_range(
+        &self,
+        f: &mut Formatter,
+        range: &RunRange,
+        options: &Options,
+    ) -> core::fmt::Result {
+        fmt_range(f, range, options)
+    }
+
+    /// Writes the separator between two runs, or after each run if `multiline` is `true`. The
+    /// default is `", "`, or `","` in the multi-line layout.
+    fn write_between(&self, f: &mut Formatter, multiline: bool) -> core::fmt::Result {
+        f.write_str(if multiline { "," } else { ", " })
+    }
+
+    /// Writes the text before the first run. The default writes nothing, or `[` in the
+    /// multi-line layout.
+    fn write_open(&self, f: &mut Formatter, multiline: bool) -> core::fmt::Result {
+        if multiline {
+            f.write_str("[")?;
+        }
+        Ok(())
+    }
+
+    /// Writes the text after the last run. The default writes nothing, or `]` in the multi-line
+    /// layout.
+    fn write_close(&self, f: &mut Formatter, multiline: bool) -> core::fmt::Result {
+        if multiline {
+            f.write_str("]")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'r, R: RunFormatter + ?Sized> RunFormatter for &'r R {
+    fn write_single(&self, f: &mut Formatter, item: &dyn Debug) -> core::fmt::Result {
+        (**self).write_single(f, item)
+    }
+
+    fn write_repeat(&self, f: &mut Formatter, item: &dyn Debug, count: usize) -> core::fmt::Result {
+        (**self).write_repeat(f, item, count)
+    }
+
+    fn write_range(
+        &self,
+        f: &mut Formatter,
+        range: &RunRange,
+        options: &Options,
+    ) -> core::fmt::Result {
+        (**self).write_range(f, range, options)
+    }
+
+    fn write_between(&self, f: &mut Formatter, multiline: bool) -> core::fmt::Result {
+        (**self).write_between(f, multiline)
+    }
+
+    fn write_open(&self, f: &mut Formatter, multiline: bool) -> core::fmt::Result {
+        (**self).write_open(f, multiline)
+    }
+
+    fn write_close(&self, f: &mut Formatter, multiline: bool) -> core::fmt::Result {
+        (**self).write_close(f, multiline)
+    }
+}
+
+/// The [`RunFormatter`] that is used unless another one is given. It writes runs separated by
+/// `", "`, with ranges written in the notation given by [`Options`], e.g. `10, 12-15, 20`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DefaultFormatter;
+
+impl RunFormatter for DefaultFormatter {}
+
+/// A run of two or more items, passed to [`RunFormatter::write_range`].
+#[derive(Copy, Clone)]
+pub struct RunRange<'r> {
+    /// The first item in the range
+    pub first: &'r dyn Debug,
+
+    /// The last item in the range
+    pub last: &'r dyn Debug,
+
+    /// The item after the last item, if the type can compute it and the range is increasing and
+    /// not strided.
+    /// This is used by [`RangeNotation::HalfOpen`](crate::RangeNotation::HalfOpen).
+    pub end: Option<&'r dyn Debug>,
+
+    /// The number of items in the range. Ranges of missing values, such as those written by
+    /// [`debug_gaps`](crate::debug_gaps), can be longer than any slice, so this is a `u128`. It
+    /// is `u128::MAX` if the number is too large to be known.
+    pub len: u128,
+
+    /// `true` if the items decrease from `first` to `last`. See
+    /// [`Direction`](crate::Direction).
+    pub descending: bool,
+
+    /// The distance between consecutive items, if it is not 1. See [`Stride`](crate::Stride).
+    pub step: Option<u128>,
+}
+
+impl<'r> RunRange<'r> {
+    /// Returns `true` if the output of `first` or `last` starts with `-`. This is what
+    /// [`NegativeStyle::Auto`](crate::NegativeStyle::Auto) uses to choose the notation.
+    pub fn is_negative(&self) -> bool {
+        is_negative(self.first) || is_negative(self.last)
+    }
+}
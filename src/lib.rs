@@ -10,17 +10,228 @@
 //! It can be helpful to display the runs as ranges, e.g. `[42, 100-104, 20, 31-34]`. This is more
 //! compact and can help the developer spot patterns in data more quickly.
 //!
-//! This crate provides two types that display ranges more compactly, and functions which construct
-//! those types.
+//! This crate provides types that display ranges more compactly, and functions which construct
+//! those types. They accept either slices or, with [`debug_adjacent_iter`] and
+//! [`debug_adjacent_ref`], any iterator that can be cloned. Bitmaps can be written as the indices
+//! of their set bits with [`debug_bits`], and lists of ranges, such as extents, can be merged
+//! with [`debug_ranges`]. Mappings from logical to physical positions can be written as extents
+//! with [`debug_extents`], and maps with integer keys, such as page tables, with
+//! [`debug_adjacent_map`].
 //!
-//! See [`debug_adjacent`] for an example.
+//! See [`debug_adjacent`] for an example. For output that is meant for users rather than
+//! developers, [`display_adjacent`] and [`display_adjacent_by`] do the same with `Display`. To
+//! write each item some other way, use [`debug_adjacent_with`]. To write the values that are
+//! missing from a sorted list instead, use [`debug_gaps`].
+//!
+//! The way that runs are written can be changed with [`Options`], which every wrapper type has
+//! as its `options` field, and which can also be set by chaining methods such as `sep` after the
+//! constructor. To change the text of the list itself, such as the brackets or the separator
+//! between runs, implement [`RunFormatter`].
+//!
+//! Lists written this way can be read back with [`parse_ranges`], or with `parse_items` if the
+//! `alloc` feature is enabled. The Linux `cpulist` format, e.g. `0-3,8-11,16`, is written by
+//! [`cpulist`] and [`cpulist_mask`] and read by [`parse_cpulist`] and [`parse_cpulist_mask`].
+//!
+//! The runs themselves are available from [`runs`] and [`runs_by`], which are the same run
+//! detection that the `Debug` implementations use, and [`run_stats`] summarizes them.
 
 #![forbid(unsafe_code)]
 #![warn(missing_docs)]
 #![allow(clippy::needless_lifetimes)]
 #![cfg_attr(not(test), no_std)]
 
-use core::fmt::{Debug, Formatter};
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+use core::borrow::Borrow;
+use core::fmt::{Debug, Display, Formatter};
+use core::marker::PhantomData;
+use core::ops::RangeInclusive;
+
+/// Generates methods that change the `options` field of a wrapper type, so that they can be
+/// chained after the constructor.
+macro_rules! option_setters {
+    ($a:lifetime) => {
+        option_setters!(runs $a);
+
+        /// Sets whether the items are written in whichever form is shortest. See
+        /// [`Options::shortest`](crate::Options::shortest).
+        pub fn shortest(mut self, shortest: bool) -> Self {
+            self.options.shortest = shortest;
+            self
+        }
+    };
+
+    // Every option other than `shortest`, which needs the items in a slice, for wrapper types that
+    // find runs in an iterator.
+    (runs $a:lifetime) => {
+        option_setters!(list $a);
+        option_setters!(range $a);
+        option_setters!(lengths $a);
+
+        /// Sets which runs of adjacent items are collapsed into ranges. See
+        /// [`Direction`](crate::Direction).
+        pub fn direction(mut self, direction: crate::Direction) -> Self {
+            self.options.direction = direction;
+            self
+        }
+
+        /// Sets whether runs with a constant step other than 1 are collapsed. See
+        /// [`Stride`](crate::Stride).
+        pub fn stride(mut self, stride: crate::Stride) -> Self {
+            self.options.stride = stride;
+            self
+        }
+
+        /// Sets whether runs of repeated items are collapsed, e.g. `0 x4`. See
+        /// [`Repeats`](crate::Repeats).
+        pub fn repeats(mut self, repeats: crate::Repeats) -> Self {
+            self.options.repeats = repeats;
+            self
+        }
+
+        /// Sets the number of consecutive missing values that a run may skip. Runs with gaps are
+        /// written with the missing values, e.g. `100-110 (missing 103, 107)`.
+        pub fn max_gap(mut self, max_gap: usize) -> Self {
+            self.options.max_gap = max_gap;
+            self
+        }
+
+        /// Sets how the values that are missing from a run are written. See
+        /// [`GapStyle`](crate::GapStyle).
+        pub fn gaps(mut self, gaps: crate::GapStyle) -> Self {
+            self.options.gaps = gaps;
+            self
+        }
+    };
+
+    // Only the options that apply to the list as a whole, for wrapper types that write each run
+    // themselves.
+    (list $a:lifetime) => {
+        /// Sets all of the options at once.
+        pub fn options(mut self, options: crate::Options<$a>) -> Self {
+            self.options = options;
+            self
+        }
+
+        /// Sets whether the output starts with the total number of items and runs, e.g.
+        /// `37 items in 5 runs: ...`.
+        pub fn header(mut self, header: bool) -> Self {
+            self.options.header = header;
+            self
+        }
+
+        /// Sets whether the list is followed by the [`RunStats`](crate::RunStats) of its runs. See
+        /// [`Options::stats`](crate::Options::stats).
+        pub fn stats(mut self, stats: bool) -> Self {
+            self.options.stats = stats;
+            self
+        }
+
+        /// Limits the output to `max_runs` runs. If there are more runs than this, then the first
+        /// half and the last half are written, with a marker such as
+        /// `... 1234 more runs (56789 items) ...` between them.
+        pub fn max_runs(mut self, max_runs: usize) -> Self {
+            self.options.head = Some(max_runs - max_runs / 2);
+            self.options.tail = Some(max_runs / 2);
+            self
+        }
+
+        /// Sets the number of runs written before the marker when the output is limited. See
+        /// [`max_runs`](Self::max_runs).
+        pub fn head(mut self, head: usize) -> Self {
+            self.options.head = Some(head);
+            self
+        }
+
+        /// Sets the number of runs written after the marker when the output is limited. See
+        /// [`max_runs`](Self::max_runs).
+        pub fn tail(mut self, tail: usize) -> Self {
+            self.options.tail = Some(tail);
+            self
+        }
+
+        /// Sets the layout used when formatting with `{:#?}`. See [`Pretty`](crate::Pretty).
+        pub fn pretty(mut self, pretty: crate::Pretty) -> Self {
+            self.options.pretty = pretty;
+            self
+        }
+    };
+
+    // The options that depend only on the number of items in each run.
+    (lengths $a:lifetime) => {
+        /// Sets the minimum number of items in a range. Shorter runs are written as individual
+        /// items, so with a minimum of 3, `[10, 11, 20, 21, 22]` is written as `10, 11, 20-22`.
+        pub fn min_run(mut self, min_run: usize) -> Self {
+            self.options.min_run = min_run;
+            self
+        }
+
+        /// Sets whether each range is followed by the number of items in it, e.g.
+        /// `0x100-0x1ff (256)`. Counts are always written in decimal.
+        pub fn counts(mut self, counts: bool) -> Self {
+            self.options.counts = counts;
+            self
+        }
+    };
+
+    // The options that choose how the endpoints of a range are written.
+    (range $a:lifetime) => {
+        /// Sets the separator between the first and last item in a range.
+        pub fn sep(mut self, sep: &$a str) -> Self {
+            self.options.sep = sep;
+            self
+        }
+
+        /// Sets the notation used for ranges. See [`RangeNotation`](crate::RangeNotation).
+        pub fn notation(mut self, notation: crate::RangeNotation) -> Self {
+            self.options.notation = notation;
+            self
+        }
+
+        /// Sets how ranges are written when an endpoint is negative. See
+        /// [`NegativeStyle`](crate::NegativeStyle).
+        pub fn negatives(mut self, negatives: crate::NegativeStyle) -> Self {
+            self.options.negatives = negatives;
+            self
+        }
+    };
+}
+
+mod bits;
+mod cpulist;
+mod extents;
+mod formatter;
+mod map;
+mod merge;
+mod options;
+mod parse;
+mod runs;
+mod stats;
+mod write;
+
+pub use bits::{debug_bits, debug_mask, BitOrder, BitWord, DebugBits};
+pub use cpulist::{
+    cpulist, cpulist_mask, parse_cpulist, parse_cpulist_mask, Cpulist, CpulistMask, ParseCpulist,
+};
+pub use extents::{debug_extents, debug_extents_indexed, DebugExtents, DebugExtentsIndexed};
+pub use formatter::{DefaultFormatter, RunFormatter, RunRange};
+pub use map::{
+    debug_adjacent_map, debug_adjacent_map_by, DebugAdjacentMap, MapEntry, MapKey, MapValue,
+    ValuesEq,
+};
+pub use merge::{debug_ranges, DebugRanges, MergeRange};
+pub use options::{
+    Direction, GapStyle, NegativeStyle, Options, Pretty, RangeNotation, Repeats, Stride,
+};
+#[cfg(feature = "alloc")]
+pub use parse::parse_items;
+pub use parse::{parse_ranges, ParseError, ParseErrorKind, ParseItem, ParseRanges};
+pub use runs::{runs, runs_by, Run, Runs};
+pub use stats::{run_stats, run_stats_by, RunStats};
+
+use runs::{GapRuns, IterRuns};
+use write::{fmt_runs, fmt_shortest, Steps};
 
 /// Returns a value that implements `Debug` by collapsing runs of "adjacent" items.
 ///
@@ -36,27 +247,183 @@ use core::fmt::{Debug, Formatter};
 ///     "10, 12-15, 20"
 /// );
 /// ```
-pub fn debug_adjacent<T: Debug + IsAdjacent>(items: &[T]) -> DebugAdjacent<T> {
+pub fn debug_adjacent<T: Debug + IsAdjacent>(items: &[T]) -> DebugAdjacent<'_, T> {
     DebugAdjacent::new(items)
 }
 
+/// Returns a value that implements `Debug` by collapsing runs of "adjacent" items produced by an
+/// iterator.
+///
+/// This accepts anything that can be iterated and cloned, such as a filtered range, a
+/// `BTreeSet::iter()` or a `VecDeque::iter()`, so that the items do not need to be collected into
+/// a slice first. `items` is cloned each time the value is formatted, and the resulting iterator
+/// is cloned in order to look ahead at the next item, so nothing is allocated.
+///
+/// The items can be values of `T`, or anything that borrows a `T`. An iterator that produces
+/// references, such as `BTreeSet::iter()`, also borrows the references themselves, so the type of
+/// `T` must be given with a turbofish, as in `debug_adjacent_iter::<u32, _>(&set)`. Use
+/// [`debug_adjacent_ref`] to avoid this.
+///
+/// # Example
+/// ```
+/// use dbg_ranges::debug_adjacent_iter;
+/// use std::collections::BTreeSet;
+///
+/// assert_eq!(
+///     format!("{:?}", debug_adjacent_iter((0..20u32).filter(|i| i % 8 < 3))),
+///     "0-2, 8-10, 16-18"
+/// );
+///
+/// let set = BTreeSet::from([1u32, 2, 3, 7]);
+/// assert_eq!(format!("{:?}", debug_adjacent_iter::<u32, _>(&set)), "1-3, 7");
+/// ```
+pub fn debug_adjacent_iter<T, I>(items: I) -> DebugAdjacentIter<'static, I, T>
+where
+    I: IntoIterator + Clone,
+    I::IntoIter: Clone,
+    I::Item: Borrow<T>,
+    T: Debug + IsAdjacent,
+{
+    DebugAdjacentIter::new(items)
+}
+
+/// Returns a value that implements `Debug` by collapsing runs of "adjacent" items produced by an
+/// iterator of references, such as `BTreeSet::iter()` or `&VecDeque`.
+///
+/// This is the same as [`debug_adjacent_iter`], except that the type of the items does not need
+/// to be given.
+///
+/// # Example
+/// ```
+/// use dbg_ranges::debug_adjacent_ref;
+/// use std::collections::BTreeSet;
+///
+/// let set = BTreeSet::from([1u32, 2, 3, 7]);
+/// assert_eq!(format!("{:?}", debug_adjacent_ref(&set)), "1-3, 7");
+/// assert_eq!(format!("{:?}", debug_adjacent_ref(set.range(2..))), "2-3, 7");
+/// ```
+pub fn debug_adjacent_ref<'t, T, I>(items: I) -> DebugAdjacentIter<'static, I, T>
+where
+    I: IntoIterator<Item = &'t T> + Clone,
+    I::IntoIter: Clone,
+    T: Debug + IsAdjacent + 't,
+{
+    DebugAdjacentIter::new(items)
+}
+
 /// Returns a value that implements `Debug` by collapsing runs of "adjacent" items.
 ///
 /// The `is_adjacent` parameter defines whether two values in `T` are adjacent.
 pub fn debug_adjacent_by<T: Debug, F: Fn(&T, &T) -> bool>(
     items: &[T],
     is_adjacent: F,
-) -> DebugAdjacentBy<T, F> {
+) -> DebugAdjacentBy<'_, T, F> {
     DebugAdjacentBy::new(items, is_adjacent)
 }
 
+/// Returns a value that implements `Debug` by collapsing runs of "adjacent" items, and which
+/// writes each item with `fmt_item`.
+///
+/// This is useful when the value that is compared for adjacency is not the text that should be
+/// shown, such as a block number written as `blk#0x1f`. The `is_adjacent` parameter defines
+/// whether two values in `T` are adjacent. To use the `IsAdjacent` trait instead, call
+/// [`DebugAdjacent::fmt_item`].
+///
+/// # Example
+/// ```
+/// use dbg_ranges::debug_adjacent_with;
+///
+/// let blocks = [0x1fu32, 0x20, 0x21, 0x40];
+/// let dump = debug_adjacent_with(&blocks, |a, b| a + 1 == *b, |blk, f| write!(f, "blk#{:#x}", blk));
+/// assert_eq!(format!("{:?}", dump), "blk#0x1f-blk#0x21, blk#0x40");
+/// ```
+pub fn debug_adjacent_with<T, F, W>(
+    items: &[T],
+    is_adjacent: F,
+    fmt_item: W,
+) -> DebugAdjacentWith<'_, T, F, W>
+where
+    F: Fn(&T, &T) -> bool,
+    W: Fn(&T, &mut Formatter) -> core::fmt::Result,
+{
+    DebugAdjacentBy::new(items, is_adjacent).fmt_item(fmt_item)
+}
+
+/// Returns a value that implements `Display` by collapsing runs of "adjacent" items.
+///
+/// This is the same as [`debug_adjacent`], except that the items are written with their
+/// `Display` implementation, which makes it suitable for error messages and other output that
+/// is meant for users.
+///
+/// # Example
+/// ```
+/// use dbg_ranges::display_adjacent;
+///
+/// let missing = [3u32, 4, 5, 9];
+/// assert_eq!(
+///     format!("blocks {} are missing", display_adjacent(&missing)),
+///     "blocks 3-5, 9 are missing"
+/// );
+/// ```
+pub fn display_adjacent<T: Display + IsAdjacent>(items: &[T]) -> DisplayAdjacent<'_, T> {
+    DisplayAdjacent::new(items)
+}
+
+/// Returns a value that implements `Display` by collapsing runs of "adjacent" items.
+///
+/// The `is_adjacent` parameter defines whether two values in `T` are adjacent.
+pub fn display_adjacent_by<T: Display, F: Fn(&T, &T) -> bool>(
+    items: &[T],
+    is_adjacent: F,
+) -> DisplayAdjacentBy<'_, T, F> {
+    DisplayAdjacentBy::new(items, is_adjacent)
+}
+
+/// Returns a value that implements `Debug` by writing the values that are missing from a sorted
+/// list, collapsing runs of missing values into ranges.
+///
+/// The missing values are the ones between the first and last items, unless another range is
+/// given with [`DebugGaps::within`]. The runs are written in the same way as those of
+/// [`debug_adjacent`], and can be changed with the same [`Options`].
+///
+/// # Example
+/// ```
+/// use dbg_ranges::debug_gaps;
+///
+/// let items = [10u32, 12, 13, 14, 15, 20];
+/// assert_eq!(format!("missing: {:?}", debug_gaps(&items)), "missing: 11, 16-19");
+///
+/// let used = [3u16, 4, 5, 1000];
+/// assert_eq!(
+///     format!("{:?}", debug_gaps(&used).within(0..=1023)),
+///     "0-2, 6-999, 1001-1023"
+/// );
+/// ```
+pub fn debug_gaps<T: Debug + Sequential>(items: &[T]) -> DebugGaps<'_, T> {
+    DebugGaps::new(items)
+}
+
 macro_rules! int_successor {
     ($t:ty) => {
         impl IsAdjacent for $t {
-            fn is_adjacent(&self, other: &Self) -> bool {
-                other.checked_sub(*self) == Some(1)
+            fn successor(&self) -> Option<Self> {
+                self.checked_add(1)
+            }
+
+            fn predecessor(&self) -> Option<Self> {
+                self.checked_sub(1)
+            }
+
+            fn distance(&self, other: &Self) -> Option<u128> {
+                if other >= self {
+                    Some(other.abs_diff(*self) as u128)
+                } else {
+                    None
+                }
             }
         }
+
+        impl Sequential for $t {}
     };
 }
 int_successor!(u8);
@@ -64,28 +431,79 @@ int_successor!(u16);
 int_successor!(u32);
 int_successor!(u64);
 int_successor!(u128);
+int_successor!(usize);
 int_successor!(i8);
 int_successor!(i16);
 int_successor!(i32);
 int_successor!(i64);
 int_successor!(i128);
+int_successor!(isize);
 
 impl IsAdjacent for char {
-    fn is_adjacent(&self, next: &Self) -> bool {
-        if let Some(after_self) = (*self as u32).checked_add(1) {
-            if let Some(after_self) = char::from_u32(after_self) {
-                after_self == *next
-            } else {
-                false
-            }
-        } else {
-            false
-        }
+    fn successor(&self) -> Option<Self> {
+        char::from_u32((*self as u32).checked_add(1)?)
+    }
+
+    fn predecessor(&self) -> Option<Self> {
+        char::from_u32((*self as u32).checked_sub(1)?)
+    }
+
+    /// Returns the difference between the code points. Chars on either side of the surrogates,
+    /// which are not chars, are more than 1 apart, so they are not adjacent.
+    fn distance(&self, other: &Self) -> Option<u128> {
+        (*other as u32).checked_sub(*self as u32).map(u128::from)
     }
 }
 
+impl Sequential for char {}
+
+/// Items that form a sequence, in which each item can step to the next and the previous item, and
+/// the distance between two items can be measured.
+///
+/// This marks a type whose [`IsAdjacent`] implementation provides
+/// [`successor`](IsAdjacent::successor), [`predecessor`](IsAdjacent::predecessor) and
+/// [`distance`](IsAdjacent::distance), which gives it everything that the wrapper types need:
+/// half-open ranges, counts of missing values, strides and repeats. [`debug_gaps`] requires it.
+/// Such a type does not need to implement [`IsAdjacent::is_adjacent`], which is derived from the
+/// distance. Implementations are provided for Rust integer types and `char`.
+///
+/// # Example
+/// ```
+/// use dbg_ranges::{debug_adjacent, IsAdjacent, Sequential, Stride};
+///
+/// #[derive(Debug)]
+/// struct BlockNumber(u64);
+///
+/// impl IsAdjacent for BlockNumber {
+///     fn successor(&self) -> Option<Self> {
+///         self.0.checked_add(1).map(BlockNumber)
+///     }
+///
+///     fn predecessor(&self) -> Option<Self> {
+///         self.0.checked_sub(1).map(BlockNumber)
+///     }
+///
+///     fn distance(&self, other: &Self) -> Option<u128> {
+///         other.0.checked_sub(self.0).map(u128::from)
+///     }
+/// }
+///
+/// impl Sequential for BlockNumber {}
+///
+/// let blocks = [1, 2, 3, 10, 20, 30].map(BlockNumber);
+/// assert_eq!(
+///     format!("{:?}", debug_adjacent(&blocks).stride(Stride::Infer)),
+///     "BlockNumber(1)-BlockNumber(3), BlockNumber(10)-BlockNumber(30)/10"
+/// );
+/// ```
+pub trait Sequential: IsAdjacent + Sized {}
+
 /// Checks whether an item is "adjacent" to another item.
 ///
+/// Types that can also step between items and measure distances should implement
+/// [`successor`](Self::successor), [`predecessor`](Self::predecessor) and
+/// [`distance`](Self::distance), and then [`Sequential`].
+///
 /// ```
 /// use dbg_ranges::IsAdjacent;
 ///
@@ -93,67 +511,198 @@ impl IsAdjacent for char {
 /// ```
 pub trait IsAdjacent {
     /// Returns `true` if `self` is adjacent to `other`.
-    fn is_adjacent(&self, other: &Self) -> bool;
+    ///
+    /// The default implementation returns `true` if [`distance`](Self::distance) is 1, so a type
+    /// that implements `distance` does not need to implement this.
+    fn is_adjacent(&self, other: &Self) -> bool {
+        self.distance(other) == Some(1)
+    }
+
+    /// Returns the item that `self` is adjacent to, if the type can compute it.
+    ///
+    /// This is used by [`RangeNotation::HalfOpen`] to find the end of a range. The default
+    /// implementation returns `None`, in which case ranges are written in inclusive notation.
+    fn successor(&self) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        None
+    }
+
+    /// Returns the item that is adjacent to `self`, if the type can compute it.
+    ///
+    /// This is used when parsing ranges in [`RangeNotation::HalfOpen`]. The default
+    /// implementation returns `None`.
+    fn predecessor(&self) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        None
+    }
+
+    /// Returns how far `other` is after `self`, if `other` is not before `self` and the type can
+    /// compute it. The distance from an item to an equal item is 0.
+    ///
+    /// This is used by [`Stride`] to find runs with a constant step, and by
+    /// [`Options::max_gap`] to find runs with gaps. The default implementation returns `None`, in
+    /// which case only runs of adjacent items are found.
+    fn distance(&self, _other: &Self) -> Option<u128> {
+        None
+    }
+
+    /// Returns `true` if `other` is a repeat of `self`, so that the two can be written once with
+    /// a count.
+    ///
+    /// This is used by [`Repeats`]. The default implementation returns `true` if
+    /// [`distance`](Self::distance) is 0, so types that cannot measure distances write repeated
+    /// items individually.
+    fn is_repeat(&self, other: &Self) -> bool {
+        self.distance(other) == Some(0)
+    }
 }
 
+/// The `IsAdjacent` function that [`DebugAdjacent::fmt_item`] uses to find runs.
+type TraitAdjacent<T> = fn(&T, &T) -> bool;
+
 /// Displays a list of integers. If the list contains sequences of contiguous (increasing) values
 /// then these will be displayed using `start-end` notation, rather than displaying each value.
 ///
 /// The user of this type provides a function which indicates whether items are "adjacent" or not.
 #[derive(Copy, Clone)]
-pub struct DebugAdjacent<'a, T> {
+pub struct DebugAdjacent<'a, T, R = DefaultFormatter> {
     /// The items that will be displayed
     pub items: &'a [T],
 
-    /// The separator between the first and last item in a range.
-    pub sep: &'a str,
+    /// Controls how the runs are written. This replaces the `sep` field of 0.1; use `options.sep`
+    /// or the `sep` method instead.
+    pub options: Options<'a>,
+
+    /// Writes the parts of the list
+    pub formatter: R,
 }
 
 impl<'a, T> DebugAdjacent<'a, T> {
     /// Constructor
     pub fn new(items: &'a [T]) -> Self {
-        Self { items, sep: "-" }
+        Self {
+            items,
+            options: Options::default(),
+            formatter: DefaultFormatter,
+        }
     }
 }
 
-impl<'a, T> Debug for DebugAdjacent<'a, T>
+impl<'a, T, R> DebugAdjacent<'a, T, R> {
+    option_setters!('a);
+
+    /// Writes each item with `fmt_item` instead of its `Debug` implementation. Adjacency is still
+    /// defined by the `IsAdjacent` trait.
+    pub fn fmt_item<W>(self, fmt_item: W) -> DebugAdjacentWith<'a, T, TraitAdjacent<T>, W, R>
+    where
+        T: IsAdjacent,
+        W: Fn(&T, &mut Formatter) -> core::fmt::Result,
+    {
+        DebugAdjacentWith {
+            items: self.items,
+            options: self.options,
+            is_adjacent: T::is_adjacent,
+            fmt_item,
+            formatter: self.formatter,
+            steps: Steps::from_trait(),
+            is_repeat: T::is_repeat,
+        }
+    }
+
+    /// Sets the [`RunFormatter`] that writes the parts of the list.
+    pub fn formatter<R2: RunFormatter>(self, formatter: R2) -> DebugAdjacent<'a, T, R2> {
+        DebugAdjacent {
+            items: self.items,
+            options: self.options,
+            formatter,
+        }
+    }
+}
+
+impl<'a, T, R> Debug for DebugAdjacent<'a, T, R>
 where
     T: Debug + IsAdjacent,
+    R: RunFormatter,
 {
     fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
-        let mut need_comma = false;
+        fmt_slice(
+            f,
+            self.items,
+            &self.options,
+            &self.formatter,
+            <T as Debug>::fmt,
+        )
+    }
+}
 
-        let mut iter = self.items.iter().peekable();
+/// Displays the items produced by an iterator, collapsing runs of adjacent values into
+/// `start-end` notation.
+///
+/// The iterator is cloned each time this value is formatted. Its items borrow values of `T`.
+/// [`Options::shortest`] is not used, since it needs the items in a slice. Use
+/// [`debug_adjacent_iter`] or [`debug_adjacent_ref`] to create this type.
+pub struct DebugAdjacentIter<'a, I, T> {
+    /// The items that will be displayed
+    pub items: I,
 
-        while let Some(first) = iter.next() {
-            if need_comma {
-                f.write_str(", ")?;
-            }
-            need_comma = true;
+    /// Controls how the runs are written
+    pub options: Options<'a>,
 
-            let mut this: &T = first;
-            let mut last: Option<&T> = None;
+    _items: PhantomData<fn(&T)>,
+}
 
-            while let Some(&next) = iter.peek() {
-                if this.is_adjacent(next) {
-                    this = next;
-                    last = Some(next);
-                    _ = iter.next();
-                } else {
-                    break;
-                }
-            }
+impl<'a, I, T> DebugAdjacentIter<'a, I, T> {
+    /// Constructor
+    pub fn new(items: I) -> Self {
+        Self {
+            items,
+            options: Options::default(),
+            _items: PhantomData,
+        }
+    }
 
-            if let Some(last) = last {
-                <T as Debug>::fmt(first, f)?;
-                f.write_str(self.sep)?;
-                <T as Debug>::fmt(last, f)?;
-            } else {
-                <T as Debug>::fmt(first, f)?;
-            }
+    option_setters!(runs 'a);
+}
+
+impl<'a, I: Copy, T> Copy for DebugAdjacentIter<'a, I, T> {}
+
+impl<'a, I: Clone, T> Clone for DebugAdjacentIter<'a, I, T> {
+    fn clone(&self) -> Self {
+        Self {
+            items: self.items.clone(),
+            options: self.options,
+            _items: PhantomData,
         }
+    }
+}
 
-        Ok(())
+impl<'a, I, T> Debug for DebugAdjacentIter<'a, I, T>
+where
+    I: IntoIterator + Clone,
+    I::IntoIter: Clone,
+    I::Item: Borrow<T>,
+    T: Debug + IsAdjacent,
+{
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        let runs = IterRuns::new(
+            self.items.clone().into_iter(),
+            |a: &I::Item, b: &I::Item| a.borrow().is_adjacent(b.borrow()),
+            |a: &I::Item, b: &I::Item| IsAdjacent::distance(a.borrow(), b.borrow()),
+            |a: &I::Item, b: &I::Item| a.borrow().is_repeat(b.borrow()),
+            &self.options,
+        );
+        fmt_runs(
+            f,
+            runs,
+            &self.options,
+            &DefaultFormatter,
+            Steps::from_trait(),
+            <T as Debug>::fmt,
+        )
     }
 }
 
@@ -162,14 +711,22 @@ where
 ///
 /// The user of this type provides a function which indicates whether items are "adjacent" or not.
 #[derive(Copy, Clone)]
-pub struct DebugAdjacentBy<'a, T, F> {
+pub struct DebugAdjacentBy<'a, T, F, R = DefaultFormatter, E = fn(&T, &T) -> bool> {
     /// The items that will be displayed
     pub items: &'a [T],
-    /// The separator between the first and last item in a range.
-    pub sep: &'a str,
+
+    /// Controls how the runs are written. This replaces the `sep` field of 0.1; use `options.sep`
+    /// or the `sep` method instead.
+    pub options: Options<'a>,
 
     /// The function that tests for adjacency
     pub is_adjacent: F,
+
+    /// Writes the parts of the list
+    pub formatter: R,
+
+    /// The function that tests whether an item is a repeat of the one before it, for [`Repeats`]
+    pub is_repeat: E,
 }
 
 impl<'a, T, F> DebugAdjacentBy<'a, T, F> {
@@ -181,53 +738,391 @@ impl<'a, T, F> DebugAdjacentBy<'a, T, F> {
         Self {
             items,
             is_adjacent,
-            sep: "-",
+            options: Options::default(),
+            formatter: DefaultFormatter,
+            is_repeat: |_, _| false,
+        }
+    }
+}
+
+impl<'a, T, F, R> DebugAdjacentBy<'a, T, F, R> {
+    /// Writes each item with `fmt_item` instead of its `Debug` implementation.
+    pub fn fmt_item<W>(self, fmt_item: W) -> DebugAdjacentWith<'a, T, F, W, R>
+    where
+        F: Fn(&T, &T) -> bool,
+        W: Fn(&T, &mut Formatter) -> core::fmt::Result,
+    {
+        DebugAdjacentWith {
+            items: self.items,
+            options: self.options,
+            is_adjacent: self.is_adjacent,
+            fmt_item,
+            formatter: self.formatter,
+            steps: Steps::none(),
+            is_repeat: self.is_repeat,
+        }
+    }
+}
+
+impl<'a, T, F, R, E> DebugAdjacentBy<'a, T, F, R, E> {
+    option_setters!('a);
+
+    /// Sets the [`RunFormatter`] that writes the parts of the list.
+    pub fn formatter<R2: RunFormatter>(self, formatter: R2) -> DebugAdjacentBy<'a, T, F, R2, E> {
+        DebugAdjacentBy {
+            items: self.items,
+            options: self.options,
+            is_adjacent: self.is_adjacent,
+            formatter,
+            is_repeat: self.is_repeat,
+        }
+    }
+
+    /// Sets the function that tests whether an item is a repeat of the one before it, and
+    /// collapses runs of repeated items if they are not already collapsed. See [`Repeats`].
+    ///
+    /// ```
+    /// use dbg_ranges::debug_adjacent_by;
+    ///
+    /// let states = ["free", "free", "free", "used"];
+    /// let dump = debug_adjacent_by(&states, |_, _| false).repeats_by(|a, b| a == b);
+    /// assert_eq!(format!("{:?}", dump), "\"free\" x3, \"used\"");
+    /// ```
+    pub fn repeats_by<E2>(self, is_repeat: E2) -> DebugAdjacentBy<'a, T, F, R, E2>
+    where
+        E2: Fn(&T, &T) -> bool,
+    {
+        let mut options = self.options;
+        if options.repeats == Repeats::Off {
+            options.repeats = Repeats::WithRanges;
+        }
+        DebugAdjacentBy {
+            items: self.items,
+            options,
+            is_adjacent: self.is_adjacent,
+            formatter: self.formatter,
+            is_repeat,
         }
     }
 }
 
-impl<'a, T, F> Debug for DebugAdjacentBy<'a, T, F>
+impl<'a, T, F, R, E> Debug for DebugAdjacentBy<'a, T, F, R, E>
 where
     T: Debug,
     F: Fn(&T, &T) -> bool,
+    R: RunFormatter,
+    E: Fn(&T, &T) -> bool,
 {
     fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
-        let mut need_comma = false;
+        let runs = IterRuns::new(
+            self.items.iter(),
+            |a: &&T, b: &&T| (self.is_adjacent)(a, b),
+            |_: &&T, _: &&T| None,
+            |a: &&T, b: &&T| (self.is_repeat)(a, b),
+            &self.options,
+        );
+        fmt_runs(
+            f,
+            runs,
+            &self.options,
+            &self.formatter,
+            Steps::none(),
+            <T as Debug>::fmt,
+        )
+    }
+}
+
+/// Displays a list of items, collapsing runs of adjacent values into ranges, and writing each
+/// item with a function rather than with its `Debug` implementation.
+///
+/// Use [`debug_adjacent_with`], [`DebugAdjacent::fmt_item`] or [`DebugAdjacentBy::fmt_item`] to
+/// create this type.
+#[derive(Copy, Clone)]
+pub struct DebugAdjacentWith<'a, T, F, W, R = DefaultFormatter> {
+    /// The items that will be displayed
+    pub items: &'a [T],
 
-        let mut iter = self.items.iter().peekable();
+    /// Controls how the runs are written
+    pub options: Options<'a>,
 
-        while let Some(first) = iter.next() {
-            if need_comma {
-                f.write_str(", ")?;
-            }
-            need_comma = true;
+    /// The function that tests for adjacency
+    pub is_adjacent: F,
 
-            let mut this: &T = first;
-            let mut last: Option<&T> = None;
+    /// The function that writes each item
+    pub fmt_item: W,
 
-            while let Some(&next) = iter.peek() {
-                if (self.is_adjacent)(this, next) {
-                    this = next;
-                    last = Some(next);
-                    _ = iter.next();
-                } else {
-                    break;
-                }
-            }
+    /// Writes the parts of the list
+    pub formatter: R,
 
-            if let Some(last) = last {
-                <T as Debug>::fmt(first, f)?;
-                f.write_str(self.sep)?;
-                <T as Debug>::fmt(last, f)?;
-            } else {
-                <T as Debug>::fmt(first, f)?;
-            }
+    /// Moves between items, for [`RangeNotation::HalfOpen`], [`Stride`] and
+    /// [`Options::max_gap`], if adjacency comes from the `IsAdjacent` trait.
+    steps: Steps<T>,
+
+    /// Finds runs of repeated items for [`Repeats`].
+    is_repeat: fn(&T, &T) -> bool,
+}
+
+impl<'a, T, F, W, R> DebugAdjacentWith<'a, T, F, W, R> {
+    option_setters!('a);
+
+    /// Sets the [`RunFormatter`] that writes the parts of the list.
+    pub fn formatter<R2: RunFormatter>(self, formatter: R2) -> DebugAdjacentWith<'a, T, F, W, R2> {
+        DebugAdjacentWith {
+            items: self.items,
+            options: self.options,
+            is_adjacent: self.is_adjacent,
+            fmt_item: self.fmt_item,
+            formatter,
+            steps: self.steps,
+            is_repeat: self.is_repeat,
         }
+    }
+}
 
-        Ok(())
+impl<'a, T, F, W, R> Debug for DebugAdjacentWith<'a, T, F, W, R>
+where
+    F: Fn(&T, &T) -> bool,
+    W: Fn(&T, &mut Formatter) -> core::fmt::Result,
+    R: RunFormatter,
+{
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        let runs = |options: &Options| {
+            IterRuns::new(
+                self.items.iter(),
+                |a: &&T, b: &&T| (self.is_adjacent)(a, b),
+                |a: &&T, b: &&T| (self.steps.distance)(a, b),
+                |a: &&T, b: &&T| (self.is_repeat)(a, b),
+                options,
+            )
+        };
+        let options = &self.options;
+        if options.shortest {
+            let items = self.items;
+            fmt_shortest(
+                f,
+                items,
+                runs,
+                options,
+                &self.formatter,
+                self.steps,
+                &self.fmt_item,
+            )
+        } else {
+            fmt_runs(
+                f,
+                runs(options),
+                options,
+                &self.formatter,
+                self.steps,
+                &self.fmt_item,
+            )
+        }
     }
 }
 
+/// Displays a list of items using their `Display` implementation. If the list contains runs of
+/// adjacent values then these will be displayed as ranges, rather than displaying each value.
+///
+/// This is the same as [`DebugAdjacent`], except for the trait that is used to write the items.
+/// Use [`display_adjacent`] to create this type.
+#[derive(Copy, Clone)]
+pub struct DisplayAdjacent<'a, T> {
+    /// The items that will be displayed
+    pub items: &'a [T],
+
+    /// Controls how the runs are written
+    pub options: Options<'a>,
+}
+
+impl<'a, T> DisplayAdjacent<'a, T> {
+    /// Constructor
+    pub fn new(items: &'a [T]) -> Self {
+        Self {
+            items,
+            options: Options::default(),
+        }
+    }
+
+    option_setters!('a);
+}
+
+impl<'a, T> Display for DisplayAdjacent<'a, T>
+where
+    T: Display + IsAdjacent,
+{
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        fmt_slice(
+            f,
+            self.items,
+            &self.options,
+            &DefaultFormatter,
+            <T as Display>::fmt,
+        )
+    }
+}
+
+/// Displays a list of items using their `Display` implementation. If the list contains runs of
+/// adjacent values then these will be displayed as ranges, rather than displaying each value.
+///
+/// This is the same as [`DebugAdjacentBy`], except for the trait that is used to write the
+/// items. Use [`display_adjacent_by`] to create this type.
+#[derive(Copy, Clone)]
+pub struct DisplayAdjacentBy<'a, T, F> {
+    /// The items that will be displayed
+    pub items: &'a [T],
+
+    /// Controls how the runs are written
+    pub options: Options<'a>,
+
+    /// The function that tests for adjacency
+    pub is_adjacent: F,
+}
+
+impl<'a, T, F> DisplayAdjacentBy<'a, T, F> {
+    /// Constructor
+    pub fn new(items: &'a [T], is_adjacent: F) -> Self
+    where
+        F: Fn(&T, &T) -> bool,
+    {
+        Self {
+            items,
+            is_adjacent,
+            options: Options::default(),
+        }
+    }
+
+    option_setters!('a);
+}
+
+impl<'a, T, F> Display for DisplayAdjacentBy<'a, T, F>
+where
+    T: Display,
+    F: Fn(&T, &T) -> bool,
+{
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        fmt_slice_by(
+            f,
+            self.items,
+            &self.is_adjacent,
+            &self.options,
+            &DefaultFormatter,
+            <T as Display>::fmt,
+        )
+    }
+}
+
+/// Displays the values that are missing from a sorted list, collapsing runs of missing values
+/// into ranges.
+///
+/// By default, the missing values are the ones between the first and last items. Use
+/// [`within`](Self::within) to give the range of values that could be present instead. Use
+/// [`debug_gaps`] to create this type.
+#[derive(Clone)]
+pub struct DebugGaps<'a, T> {
+    /// The items that are present, in increasing order
+    pub items: &'a [T],
+
+    /// Controls how the runs are written. Each run of missing values is found without skipping
+    /// any value, so the options that change how runs are found, other than
+    /// [`Options::min_run`], are not used.
+    pub options: Options<'a>,
+
+    /// The values that could be present, or `None` for the first item to the last item
+    pub within: Option<RangeInclusive<T>>,
+}
+
+impl<'a, T> DebugGaps<'a, T> {
+    /// Constructor
+    pub fn new(items: &'a [T]) -> Self {
+        Self {
+            items,
+            options: Options::default(),
+            within: None,
+        }
+    }
+
+    /// Sets the range of values that could be present, so that values before the first item and
+    /// after the last item are also missing.
+    pub fn within(mut self, within: RangeInclusive<T>) -> Self {
+        self.within = Some(within);
+        self
+    }
+
+    option_setters!(list 'a);
+    option_setters!(range 'a);
+    option_setters!(lengths 'a);
+}
+
+impl<'a, T> Debug for DebugGaps<'a, T>
+where
+    T: Debug + Sequential,
+{
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        let bounds = match &self.within {
+            Some(within) => Some((within.start(), within.end())),
+            None => self.items.first().zip(self.items.last()),
+        };
+        let runs = GapRuns::new(
+            self.items,
+            bounds,
+            Steps::from_trait(),
+            self.options.min_run,
+        );
+        fmt_runs(
+            f,
+            runs,
+            &self.options,
+            &DefaultFormatter,
+            Steps::from_trait(),
+            <T as Debug>::fmt,
+        )
+    }
+}
+
+/// Writes the runs in a slice, using `IsAdjacent` to find them.
+fn fmt_slice<T: IsAdjacent>(
+    f: &mut Formatter,
+    items: &[T],
+    options: &Options,
+    formatter: &dyn RunFormatter,
+    write_item: impl Fn(&T, &mut Formatter) -> core::fmt::Result,
+) -> core::fmt::Result {
+    let runs = |options: &Options| {
+        IterRuns::new(
+            items.iter(),
+            |a: &&T, b: &&T| a.is_adjacent(b),
+            |a: &&T, b: &&T| T::distance(a, b),
+            |a: &&T, b: &&T| T::is_repeat(a, b),
+            options,
+        )
+    };
+    let steps = Steps::from_trait();
+    if options.shortest {
+        fmt_shortest(f, items, runs, options, formatter, steps, write_item)
+    } else {
+        fmt_runs(f, runs(options), options, formatter, steps, write_item)
+    }
+}
+
+/// Writes the runs in a slice, using a function to find them.
+fn fmt_slice_by<T, F: Fn(&T, &T) -> bool>(
+    f: &mut Formatter,
+    items: &[T],
+    is_adjacent: &F,
+    options: &Options,
+    formatter: &dyn RunFormatter,
+    write_item: impl Fn(&T, &mut Formatter) -> core::fmt::Result,
+) -> core::fmt::Result {
+    let runs = IterRuns::new(
+        items.iter(),
+        |a: &&T, b: &&T| is_adjacent(a, b),
+        |_: &&T, _: &&T| None,
+        |_: &&T, _: &&T| false,
+        options,
+    );
+    fmt_runs(f, runs, options, formatter, Steps::none(), write_item)
+}
+
 #[test]
 fn test_dump_ranges() {
     macro_rules! case {
@@ -251,7 +1146,43 @@ fn test_dump_ranges() {
     case!([10u32, 11, 20], "10-11, 20");
     case!([10u32, 12, 13, 14, 15, 20], "10, 12-15, 20");
     case!([u32::MAX, 42], "4294967295, 42");
-    case!([i32::MIN, i32::MIN + 1, 42], "-2147483648--2147483647, 42");
+    case!(
+        [i32::MIN, i32::MIN + 1, 42],
+        "-2147483648..=-2147483647, 42"
+    );
+}
+
+#[test]
+fn test_dump_ranges_negative() {
+    let items = [-5i32, -4, -3, 7, -1, 0, 1, 10, 11];
+    assert_eq!(
+        format!("{:?}", debug_adjacent(&items)),
+        "-5..=-3, 7, -1..=1, 10-11"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent(&items).negatives(NegativeStyle::Parenthesize)
+        ),
+        "(-5)-(-3), 7, (-1)-1, 10-11"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent(&items).negatives(NegativeStyle::Keep)
+        ),
+        "-5--3, 7, -1-1, 10-11"
+    );
+
+    // A separator without '-' is never ambiguous.
+    assert_eq!(
+        format!("{:?}", debug_adjacent(&items).sep(" to ")),
+        "-5 to -3, 7, -1 to 1, 10 to 11"
+    );
+    assert_eq!(
+        format!("{:?}", debug_adjacent_by(&items, |a, b| a + 1 == *b)),
+        "-5..=-3, 7, -1..=1, 10-11"
+    );
 }
 
 #[test]
@@ -301,3 +1232,1191 @@ fn test_dump_ranges_by_swapped() {
     case!([10u32, 11, 20], "10-11, 20");
     case!([10u32, 12, 13, 14, 15, 20], "10, 12-15, 20");
 }
+
+#[test]
+fn test_dump_ranges_iter() {
+    use std::collections::{BTreeSet, VecDeque};
+
+    let set: BTreeSet<u32> = [20, 1, 2, 3, 7].into_iter().collect();
+    assert_eq!(
+        format!("{:?}", debug_adjacent_iter::<u32, _>(set.iter())),
+        "1-3, 7, 20"
+    );
+    assert_eq!(
+        format!("{:?}", debug_adjacent_iter::<u32, _>(&set)),
+        "1-3, 7, 20"
+    );
+
+    // Borrowed items use the successor and distance of the values they borrow.
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent_iter::<u32, _>(&set)
+                .notation(RangeNotation::HalfOpen)
+                .max_gap(3)
+        ),
+        "1..8 (missing 4, 5, 6), 20"
+    );
+
+    // A VecDeque that wraps around stores its items in two slices.
+    let mut deque: VecDeque<u8> = VecDeque::with_capacity(4);
+    deque.extend([0, 1, 2, 3]);
+    deque.pop_front();
+    deque.pop_front();
+    deque.extend([4, 9]);
+    assert_eq!(format!("{:?}", debug_adjacent_ref(deque.iter())), "2-4, 9");
+
+    assert_eq!(
+        format!("{:?}", debug_adjacent_iter("abcx".chars())),
+        "'a'-'c', 'x'"
+    );
+    assert_eq!(format!("{:?}", debug_adjacent_iter(0..0u64)), "");
+
+    let evens = debug_adjacent_iter((0..10u64).filter(|i| i % 5 != 4));
+    assert_eq!(format!("{:?}", evens), "0-3, 5-8");
+    assert_eq!(format!("{:?}", evens), "0-3, 5-8");
+}
+
+#[test]
+fn test_dump_ranges_notation() {
+    let items = [-3i8, -2, 5, 126, 127];
+    let show = |notation| format!("{:?}", debug_adjacent(&items).notation(notation));
+    assert_eq!(show(RangeNotation::Dash), "-3..=-2, 5, 126-127");
+    assert_eq!(show(RangeNotation::Inclusive), "-3..=-2, 5, 126..=127");
+    // 127i8 has no successor, so the last range falls back to inclusive notation.
+    assert_eq!(show(RangeNotation::HalfOpen), "-3..-1, 5, 126..=127");
+    assert_eq!(show(RangeNotation::Interval), "[-3, -2], 5, [126, 127]");
+    assert_eq!(show(RangeNotation::Colon), "-3:-2, 5, 126:127");
+
+    let chars = ['a', 'b', 'c'];
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent(&chars).notation(RangeNotation::HalfOpen)
+        ),
+        "'a'..'d'"
+    );
+
+    // The closure form cannot compute a successor.
+    let by = debug_adjacent_by(&[1u32, 2, 3], |a, b| a + 1 == *b);
+    assert_eq!(
+        format!("{:?}", by.notation(RangeNotation::HalfOpen)),
+        "1..=3"
+    );
+    assert_eq!(format!("{:?}", by.notation(RangeNotation::Colon)), "1:3");
+}
+
+#[test]
+fn test_dump_ranges_min_run() {
+    let items = [10u32, 11, 20, 21, 22, 30, 40, 41, 42, 43];
+    let show = |min_run| format!("{:?}", debug_adjacent(&items).min_run(min_run));
+    assert_eq!(show(0), "10-11, 20-22, 30, 40-43");
+    assert_eq!(show(2), "10-11, 20-22, 30, 40-43");
+    assert_eq!(show(3), "10, 11, 20-22, 30, 40-43");
+    assert_eq!(show(5), "10, 11, 20, 21, 22, 30, 40, 41, 42, 43");
+
+    let by = debug_adjacent_by(&items, |a, b| a + 1 == *b).min_run(4);
+    assert_eq!(format!("{:?}", by), "10, 11, 20, 21, 22, 30, 40-43");
+
+    let iter = debug_adjacent_iter::<u32, _>(items.iter()).min_run(3);
+    assert_eq!(format!("{:?}", iter), "10, 11, 20-22, 30, 40-43");
+}
+
+#[test]
+fn test_dump_ranges_counts() {
+    let blocks = [0x100u32, 0x101, 0x102, 0x1ff, 0x200, 0x300];
+    assert_eq!(
+        format!(
+            "{:#x?}",
+            debug_adjacent(&blocks).counts(true).pretty(Pretty::Off)
+        ),
+        "0x100-0x102 (3), 0x1ff-0x200 (2), 0x300"
+    );
+    assert_eq!(
+        format!("{:?}", debug_adjacent(&blocks).header(true)),
+        "6 items in 3 runs: 256-258, 511-512, 768"
+    );
+    assert_eq!(
+        format!("{:?}", debug_adjacent(&[7u8]).header(true).counts(true)),
+        "1 item in 1 run: 7"
+    );
+    assert_eq!(
+        format!("{:?}", debug_adjacent::<u8>(&[]).header(true)),
+        "0 items in 0 runs"
+    );
+
+    // For the closure form, the count is the number of elements in the run.
+    let by = debug_adjacent_by(&[1u32, 1, 1, 5], |a, b| a == b);
+    assert_eq!(
+        format!("{:?}", by.counts(true).header(true)),
+        "4 items in 2 runs: 1-1 (3), 5"
+    );
+}
+
+#[test]
+fn test_dump_ranges_elide() {
+    // Runs: 0-1, 3, 5-7, 9, 11, 13-14
+    let items = [0u32, 1, 3, 5, 6, 7, 9, 11, 13, 14];
+    let show = |dump: DebugAdjacent<u32>| format!("{:?}", dump);
+
+    assert_eq!(
+        show(debug_adjacent(&items).max_runs(4)),
+        "0-1, 3, ... 2 more runs (4 items) ..., 11, 13-14"
+    );
+    assert_eq!(
+        show(debug_adjacent(&items).max_runs(5)),
+        "0-1, 3, 5-7, ... 1 more run (1 item) ..., 11, 13-14"
+    );
+    assert_eq!(
+        show(debug_adjacent(&items).head(1)),
+        "0-1, ... 5 more runs (8 items) ..."
+    );
+    assert_eq!(
+        show(debug_adjacent(&items).tail(2)),
+        "... 4 more runs (7 items) ..., 11, 13-14"
+    );
+    assert_eq!(
+        show(debug_adjacent(&items).max_runs(6)),
+        "0-1, 3, 5-7, 9, 11, 13-14"
+    );
+    assert_eq!(
+        show(debug_adjacent(&items).max_runs(2).header(true).counts(true)),
+        "10 items in 6 runs: 0-1 (2), ... 4 more runs (6 items) ..., 13-14 (2)"
+    );
+    assert_eq!(
+        show(debug_adjacent(&items).max_runs(0)),
+        "... 6 more runs (10 items) ..."
+    );
+}
+
+#[test]
+fn test_dump_ranges_pretty() {
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    struct File<'a> {
+        name: &'a str,
+        blocks: DebugAdjacent<'a, u32>,
+    }
+
+    let blocks = [10u32, 11, 12, 20, 30, 31];
+    let file = File {
+        name: "a.txt",
+        blocks: debug_adjacent(&blocks),
+    };
+    assert_eq!(
+        format!("{:#?}", file),
+        "\
+File {
+    name: \"a.txt\",
+    blocks: [
+        10-12,
+        20,
+        30-31,
+    ],
+}"
+    );
+    assert_eq!(
+        format!("{:?}", file),
+        "File { name: \"a.txt\", blocks: 10-12, 20, 30-31 }"
+    );
+
+    let wrapped = debug_adjacent(&blocks)
+        .pretty(Pretty::Wrap(16))
+        .counts(true);
+    assert_eq!(
+        format!("{:#?}", wrapped),
+        "[\n    10-12 (3),\n    20,\n    30-31 (2),\n]"
+    );
+    let wrapped = debug_adjacent(&blocks).pretty(Pretty::Wrap(20));
+    assert_eq!(
+        format!("{:#?}", wrapped),
+        "[\n    10-12, 20,\n    30-31,\n]"
+    );
+
+    assert_eq!(format!("{:#?}", debug_adjacent::<u32>(&[])), "[]");
+    assert_eq!(
+        format!("{:#?}", debug_adjacent(&blocks).max_runs(2).header(true)),
+        "6 items in 3 runs: [\n    10-12,\n    ... 1 more run (1 item) ...,\n    30-31,\n]"
+    );
+
+    // Items that are written on several lines are indented inside the list.
+    #[derive(Debug)]
+    struct B(u8);
+
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    struct Inode<'a> {
+        blocks: &'a dyn Debug,
+    }
+
+    let blocks = [B(1), B(2), B(4)];
+    let inode = Inode {
+        blocks: &debug_adjacent_by(&blocks, |a, b| a.0 + 1 == b.0),
+    };
+    assert_eq!(
+        format!("{:#?}", inode),
+        "\
+Inode {
+    blocks: [
+        B(
+            1,
+        )-B(
+            2,
+        ),
+        B(
+            4,
+        ),
+    ],
+}"
+    );
+}
+
+#[test]
+fn test_dump_ranges_padding() {
+    let items = [1u32, 2, 3, 5];
+    assert_eq!(format!("{:12?}|", debug_adjacent(&items)), "1-3, 5      |");
+    assert_eq!(format!("{:>12?}|", debug_adjacent(&items)), "      1-3, 5|");
+    assert_eq!(
+        format!("{:*^12?}|", debug_adjacent(&items)),
+        "***1-3, 5***|"
+    );
+    assert_eq!(format!("{:3?}|", debug_adjacent(&items)), "1-3, 5|");
+    assert_eq!(
+        format!("{:<8?}|", debug_adjacent_iter::<i8, _>([-3i8, -2].iter())),
+        "-3..=-2 |"
+    );
+
+    // The caller's other flags are kept when the list is padded.
+    let items = [0x100u32, 0x101, 0x102, 0x200];
+    assert_eq!(
+        format!("{:>30x?}|", debug_adjacent(&items)),
+        "                  100-102, 200|"
+    );
+    assert_eq!(
+        format!("{:<14X?}|", debug_adjacent(&[0xabu8, 0xac])),
+        "AB-AC         |"
+    );
+    assert_eq!(
+        format!("{:^+12?}|", debug_adjacent(&[1i32, 2, 3])),
+        "   +1-+3    |"
+    );
+    assert_eq!(
+        format!("{:#24x?}|", debug_adjacent(&items).pretty(Pretty::Off)),
+        "0x100-0x102, 0x200      |"
+    );
+}
+
+#[test]
+fn test_display_ranges() {
+    struct Block(u32);
+
+    impl Display for Block {
+        fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+            write!(f, "blk{}", self.0)
+        }
+    }
+
+    impl IsAdjacent for Block {
+        fn is_adjacent(&self, other: &Self) -> bool {
+            self.0.is_adjacent(&other.0)
+        }
+    }
+
+    let blocks = [Block(1), Block(2), Block(3), Block(7)];
+    assert_eq!(format!("{}", display_adjacent(&blocks)), "blk1-blk3, blk7");
+    assert_eq!(
+        format!(
+            "{}",
+            display_adjacent(&blocks).notation(RangeNotation::Interval)
+        ),
+        "[blk1, blk3], blk7"
+    );
+    assert_eq!(
+        format!("{:>20}|", display_adjacent(&blocks)),
+        "     blk1-blk3, blk7|"
+    );
+
+    let names = ["a", "b", "c", "x"];
+    let letters = display_adjacent_by(&names, |a, b| {
+        a.len() == 1 && b.len() == 1 && a.as_bytes()[0] + 1 == b.as_bytes()[0]
+    });
+    assert_eq!(format!("{}", letters), "a-c, x");
+    assert_eq!(format!("{}", letters.counts(true)), "a-c (3), x");
+
+    let values = [-2i32, -1, 0, 4];
+    assert_eq!(format!("{}", display_adjacent(&values)), "-2..=0, 4");
+    assert_eq!(
+        format!("{}", display_adjacent(&values).header(true)),
+        format!("{:?}", debug_adjacent(&values).header(true))
+    );
+}
+
+#[test]
+fn test_dump_ranges_with() {
+    let syscalls = [0u32, 1, 2, 9, 60];
+    let name = |n: &u32, f: &mut Formatter| match n {
+        0 => f.write_str("read"),
+        1 => f.write_str("write"),
+        2 => f.write_str("open"),
+        9 => f.write_str("mmap"),
+        _ => write!(f, "sys#{}", n),
+    };
+
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent_with(&syscalls, |a, b| a + 1 == *b, name)
+        ),
+        "read-open, mmap, sys#60"
+    );
+    assert_eq!(
+        format!("{:?}", debug_adjacent(&syscalls).fmt_item(name)),
+        "read-open, mmap, sys#60"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent_by(&syscalls, |a, b| a + 1 == *b)
+                .fmt_item(name)
+                .counts(true)
+        ),
+        "read-open (3), mmap, sys#60"
+    );
+
+    // The trait form can still compute a successor, which is written with the same function.
+    let half_open = debug_adjacent(&syscalls)
+        .fmt_item(name)
+        .notation(RangeNotation::HalfOpen);
+    assert_eq!(format!("{:?}", half_open), "read..sys#3, mmap, sys#60");
+    let half_open =
+        debug_adjacent_with(&syscalls, |a, b| a + 1 == *b, name).notation(RangeNotation::HalfOpen);
+    assert_eq!(format!("{:?}", half_open), "read..=open, mmap, sys#60");
+
+    let signed = [-2i32, -1, 5];
+    let dump = debug_adjacent(&signed).fmt_item(|n, f| write!(f, "#{}", n));
+    assert_eq!(format!("{:?}", dump), "#-2-#-1, #5");
+    assert_eq!(format!("{:>14?}|", dump), "   #-2-#-1, #5|");
+}
+
+#[test]
+fn test_dump_ranges_formatter() {
+    struct Brackets;
+
+    impl RunFormatter for Brackets {
+        fn write_single(&self, f: &mut Formatter, item: &dyn Debug) -> core::fmt::Result {
+            f.write_str("<")?;
+            item.fmt(f)?;
+            f.write_str(">")
+        }
+
+        fn write_range(
+            &self,
+            f: &mut Formatter,
+            range: &RunRange,
+            _options: &Options,
+        ) -> core::fmt::Result {
+            f.write_str("[")?;
+            range.first.fmt(f)?;
+            f.write_str("..")?;
+            range.last.fmt(f)?;
+            write!(f, "]x{}", range.len)
+        }
+
+        fn write_between(&self, f: &mut Formatter, multiline: bool) -> core::fmt::Result {
+            f.write_str(if multiline { ";" } else { "\t" })
+        }
+
+        fn write_open(&self, f: &mut Formatter, _multiline: bool) -> core::fmt::Result {
+            f.write_str("{")
+        }
+
+        fn write_close(&self, f: &mut Formatter, _multiline: bool) -> core::fmt::Result {
+            f.write_str("}")
+        }
+    }
+
+    let items = [1u32, 2, 3, 7, 9, 10];
+    assert_eq!(
+        format!("{:?}", debug_adjacent(&items).formatter(Brackets)),
+        "{[1..3]x3\t<7>\t[9..10]x2}"
+    );
+    assert_eq!(
+        format!(
+            "{:x?}",
+            debug_adjacent_by(&items, |a, b| a + 1 == *b)
+                .formatter(&Brackets)
+                .counts(true)
+        ),
+        "{[1..3]x3 (3)\t<7>\t[9..a]x2 (2)}"
+    );
+    assert_eq!(
+        format!("{:?}", debug_adjacent::<u32>(&[]).formatter(Brackets)),
+        "{}"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent::<u32>(&[]).formatter(Brackets).header(true)
+        ),
+        "0 items in 0 runs: {}"
+    );
+    assert_eq!(
+        format!(
+            "{:#?}",
+            debug_adjacent::<u32>(&[]).formatter(Brackets).header(true)
+        ),
+        "0 items in 0 runs: {}"
+    );
+    assert_eq!(
+        format!("{:?}", debug_adjacent::<u32>(&[]).header(true)),
+        "0 items in 0 runs"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent(&items).max_runs(2).formatter(Brackets)
+        ),
+        "{[1..3]x3\t... 1 more run (1 item) ...\t[9..10]x2}"
+    );
+    assert_eq!(
+        format!("{:#?}", debug_adjacent(&items).formatter(Brackets)),
+        "{\n    [1..3]x3;\n    <7>;\n    [9..10]x2;\n}"
+    );
+    assert_eq!(
+        format!(
+            "{:#?}",
+            debug_adjacent(&items)
+                .formatter(Brackets)
+                .pretty(Pretty::Wrap(30))
+        ),
+        "{\n    [1..3]x3; <7>; [9..10]x2;\n}"
+    );
+
+    // Items written by a function use the formatter too.
+    let name = |n: &u32, f: &mut Formatter| write!(f, "#{}", n);
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent(&items).formatter(Brackets).fmt_item(name)
+        ),
+        "{[#1..#3]x3\t<#7>\t[#9..#10]x2}"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent_by(&items, |a, b| a + 1 == *b)
+                .fmt_item(name)
+                .formatter(Brackets)
+        ),
+        "{[#1..#3]x3\t<#7>\t[#9..#10]x2}"
+    );
+
+    // Overriding only the separator keeps the default notation for ranges.
+    struct Semicolons;
+
+    impl RunFormatter for Semicolons {
+        fn write_between(&self, f: &mut Formatter, _multiline: bool) -> core::fmt::Result {
+            f.write_str("; ")
+        }
+    }
+
+    let signed = [-3i32, -2, 4, 5];
+    assert_eq!(
+        format!("{:?}", debug_adjacent(&signed).formatter(Semicolons)),
+        "-3..=-2; 4-5"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent(&signed)
+                .formatter(Semicolons)
+                .notation(RangeNotation::HalfOpen)
+        ),
+        "-3..-1; 4..6"
+    );
+}
+
+#[test]
+fn test_dump_ranges_direction() {
+    let blocks = [20u32, 19, 18, 17, 5, 6, 7, 3, 2];
+    let show = |direction| format!("{:?}", debug_adjacent(&blocks).direction(direction));
+    assert_eq!(show(Direction::Ascending), "20, 19, 18, 17, 5-7, 3, 2");
+    assert_eq!(show(Direction::Descending), "20-17, 5, 6, 7, 3-2");
+    assert_eq!(show(Direction::Both), "20-17, 5-7, 3-2");
+
+    // A run does not change direction part of the way through.
+    let zigzag = [1u8, 2, 1, 0, 1];
+    assert_eq!(
+        format!("{:?}", debug_adjacent(&zigzag).direction(Direction::Both)),
+        "1-2, 1-0, 1"
+    );
+
+    let max = [u128::MAX, u128::MAX - 1, 0, 1];
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent(&max).direction(Direction::Both).counts(true)
+        ),
+        "340282366920938463463374607431768211455-340282366920938463463374607431768211454 (2), 0-1 (2)"
+    );
+
+    let min = [i128::MIN + 1, i128::MIN, i128::MAX];
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent(&min).direction(Direction::Descending)
+        ),
+        "-170141183460469231731687303715884105727..=-170141183460469231731687303715884105728, \
+         170141183460469231731687303715884105727"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent(&[1i8, 0, -1, -2])
+                .direction(Direction::Both)
+                .negatives(NegativeStyle::Parenthesize)
+        ),
+        "1-(-2)"
+    );
+
+    // U+D7FF and U+E000 are not adjacent, because the surrogates between them are not chars.
+    let chars = [
+        'c',
+        'b',
+        'a',
+        '\u{e000}',
+        '\u{d7ff}',
+        char::MAX,
+        '\u{10fffe}',
+    ];
+    assert_eq!(
+        format!("{:?}", debug_adjacent(&chars).direction(Direction::Both)),
+        "'c'-'a', '\\u{e000}', '\\u{d7ff}', '\\u{10ffff}'-'\\u{10fffe}'"
+    );
+
+    // Descending ranges cannot be half-open, so they are written as inclusive ranges.
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent(&blocks)
+                .direction(Direction::Both)
+                .notation(RangeNotation::HalfOpen)
+        ),
+        "20..=17, 5..8, 3..=2"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent_by(&blocks, |a, b| a + 1 == *b)
+                .direction(Direction::Both)
+                .min_run(3)
+        ),
+        "20-17, 5-7, 3, 2"
+    );
+}
+
+#[test]
+fn test_dump_ranges_stride() {
+    let pages = [0u32, 4, 8, 12, 16, 3, 5, 7, 8, 9];
+    let show = |stride| format!("{:?}", debug_adjacent(&pages).stride(stride));
+    assert_eq!(show(Stride::Off), "0, 4, 8, 12, 16, 3, 5, 7-9");
+    assert_eq!(show(Stride::Fixed(4)), "0-16/4, 3, 5, 7-9");
+    assert_eq!(show(Stride::Fixed(2)), "0, 4, 8, 12, 16, 3-7/2, 8-9");
+    assert_eq!(show(Stride::Infer), "0-16/4, 3-7/2, 8-9");
+
+    // A step of 0 does not collapse repeated items, which is left to `Repeats`.
+    assert_eq!(show(Stride::Fixed(0)), "0, 4, 8, 12, 16, 3, 5, 7-9");
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent(&[7u32, 7, 7]).stride(Stride::Fixed(0))
+        ),
+        "7, 7, 7"
+    );
+
+    // Two items are not enough to establish a stride.
+    let pairs = [1u32, 5, 6, 7, 20, 30];
+    assert_eq!(
+        format!("{:?}", debug_adjacent(&pairs).stride(Stride::Infer)),
+        "1, 5-7, 20, 30"
+    );
+
+    let strided = debug_adjacent(&pages).stride(Stride::Infer);
+    assert_eq!(
+        format!("{:?}", strided.notation(RangeNotation::Inclusive)),
+        "0..=16 step 4, 3..=7 step 2, 8..=9"
+    );
+    assert_eq!(
+        format!("{:?}", strided.notation(RangeNotation::HalfOpen)),
+        "0..=16 step 4, 3..=7 step 2, 8..10"
+    );
+    assert_eq!(
+        format!("{:?}", strided.notation(RangeNotation::Interval)),
+        "[0, 16]/4, [3, 7]/2, [8, 9]"
+    );
+    assert_eq!(
+        format!(
+            "{:#x?}",
+            strided.counts(true).pretty(Pretty::Off).header(true)
+        ),
+        "10 items in 3 runs: 0x0-0x10/0x4 (5), 0x3-0x7/0x2 (3), 0x8-0x9 (2)"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent(&[12u32, 8, 4, 0])
+                .stride(Stride::Infer)
+                .direction(Direction::Both)
+        ),
+        "12-0/4"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent_iter::<u32, _>(pages.iter()).stride(Stride::Fixed(4))
+        ),
+        "0-16/4, 3, 5, 7-9"
+    );
+
+    // The closure form cannot measure distances, so only adjacent items are collapsed.
+    let by = debug_adjacent_by(&pages, |a, b| a + 1 == *b).stride(Stride::Infer);
+    assert_eq!(format!("{:?}", by), "0, 4, 8, 12, 16, 3, 5, 7-9");
+
+    macro_rules! case {
+        ($t:ty) => {
+            assert_eq!(
+                IsAdjacent::distance(&<$t>::MIN, &<$t>::MAX),
+                Some(<$t>::MAX.abs_diff(<$t>::MIN) as u128)
+            );
+            assert_eq!(IsAdjacent::distance(&<$t>::MAX, &<$t>::MIN), None);
+            assert_eq!(IsAdjacent::distance(&<$t>::MAX, &<$t>::MAX), Some(0));
+            let items = [<$t>::MAX - 6, <$t>::MAX - 3, <$t>::MAX];
+            assert_eq!(
+                format!("{:?}", debug_adjacent(&items).stride(Stride::Infer)),
+                format!("{}-{}/3", <$t>::MAX - 6, <$t>::MAX)
+            );
+        };
+    }
+    case!(u8);
+    case!(u16);
+    case!(u32);
+    case!(u64);
+    case!(u128);
+    case!(i8);
+    case!(i16);
+    case!(i32);
+    case!(i64);
+    case!(i128);
+
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent(&[i128::MIN, -1, i128::MAX - 1]).stride(Stride::Infer)
+        ),
+        "-170141183460469231731687303715884105728..=170141183460469231731687303715884105726 \
+         step 170141183460469231731687303715884105727"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent(&[0u128, u128::MAX / 2, u128::MAX - 1]).stride(Stride::Infer)
+        ),
+        "0-340282366920938463463374607431768211454/170141183460469231731687303715884105727"
+    );
+}
+
+#[test]
+fn test_dump_ranges_repeats() {
+    let states = [0u8, 0, 0, 0, 1, 1, 7];
+    let show = |repeats| format!("{:?}", debug_adjacent(&states).repeats(repeats));
+    assert_eq!(show(Repeats::Off), "0, 0, 0, 0-1, 1, 7");
+    assert_eq!(show(Repeats::Only), "0 x4, 1 x2, 7");
+    assert_eq!(show(Repeats::WithRanges), "0 x4, 1 x2, 7");
+
+    let items = [5u32, 5, 5, 6, 7, 8, 8];
+    let show = |repeats| format!("{:?}", debug_adjacent(&items).repeats(repeats));
+    assert_eq!(show(Repeats::Only), "5 x3, 6, 7, 8 x2");
+    assert_eq!(show(Repeats::WithRanges), "5 x3, 6-8, 8");
+
+    let dump = debug_adjacent(&items).repeats(Repeats::WithRanges);
+    assert_eq!(
+        format!("{:?}", dump.counts(true).header(true)),
+        "7 items in 3 runs: 5 x3, 6-8 (3), 8"
+    );
+    assert_eq!(format!("{:?}", dump.min_run(4)), "5, 5, 5-8, 8");
+    assert_eq!(
+        format!("{:#x?}", dump.pretty(Pretty::Off)),
+        "0x5 x3, 0x6-0x8, 0x8"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent_iter("aaab".chars()).repeats(Repeats::Only)
+        ),
+        "'a' x3, 'b'"
+    );
+
+    // Types without an `IsAdjacent` notion of repeats can use a function instead.
+    #[derive(Copy, Clone, Debug)]
+    struct Page {
+        refs: u32,
+    }
+
+    let pages = [Page { refs: 1 }, Page { refs: 1 }, Page { refs: 2 }];
+    let by = debug_adjacent_by(&pages, |a, b| a.refs + 1 == b.refs);
+    assert_eq!(
+        format!("{:?}", by.repeats(Repeats::Only)),
+        "Page { refs: 1 }, Page { refs: 1 }, Page { refs: 2 }"
+    );
+    let by = by.repeats_by(|a, b| a.refs == b.refs);
+    assert_eq!(format!("{:?}", by), "Page { refs: 1 } x2, Page { refs: 2 }");
+    assert_eq!(
+        format!("{:?}", by.repeats(Repeats::Off)),
+        "Page { refs: 1 }, Page { refs: 1 }-Page { refs: 2 }"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent(&states)
+                .repeats(Repeats::Only)
+                .fmt_item(|n, f| write!(f, "#{}", n))
+        ),
+        "#0 x4, #1 x2, #7"
+    );
+}
+
+#[test]
+fn test_dump_ranges_gaps() {
+    let blocks = [100u32, 101, 102, 104, 105, 106, 108, 109, 110, 115, 116];
+    let show = |dump: DebugAdjacent<u32>| format!("{:?}", dump);
+    assert_eq!(
+        show(debug_adjacent(&blocks)),
+        "100-102, 104-106, 108-110, 115-116"
+    );
+    assert_eq!(
+        show(debug_adjacent(&blocks).max_gap(1)),
+        "100-110 (missing 103, 107), 115-116"
+    );
+    assert_eq!(
+        show(debug_adjacent(&blocks).max_gap(4)),
+        "100-116 (missing 103, 107, 111, 112, 113, 114)"
+    );
+    assert_eq!(
+        show(debug_adjacent(&blocks).max_gap(4).gaps(GapStyle::Count)),
+        "100-116 (-6)"
+    );
+    assert_eq!(
+        show(debug_adjacent(&blocks).max_gap(1).counts(true).header(true)),
+        "11 items in 2 runs: 100-110 (9) (missing 103, 107), 115-116 (2)"
+    );
+    assert_eq!(
+        format!(
+            "{:#x?}",
+            debug_adjacent(&blocks).max_gap(1).pretty(Pretty::Off)
+        ),
+        "0x64-0x6e (missing 0x67, 0x6b), 0x73-0x74"
+    );
+    assert_eq!(
+        show(
+            debug_adjacent(&blocks)
+                .max_gap(1)
+                .notation(RangeNotation::HalfOpen)
+        ),
+        "100..111 (missing 103, 107), 115..117"
+    );
+
+    // A gap may not start or end a run.
+    assert_eq!(
+        show(debug_adjacent(&[1u32, 3, 5]).max_gap(1)),
+        "1-5 (missing 2, 4)"
+    );
+    assert_eq!(
+        show(debug_adjacent(&[1u32, 3]).max_gap(1)),
+        "1-3 (missing 2)"
+    );
+    assert_eq!(show(debug_adjacent(&[1u32, 4]).max_gap(1)), "1, 4");
+
+    let down = [-1i64, -3, -4, -7];
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent(&down)
+                .max_gap(2)
+                .direction(Direction::Descending)
+        ),
+        "-1..=-7 (missing -2, -5, -6)"
+    );
+    assert_eq!(
+        format!("{:?}", debug_adjacent_iter(['a', 'c', 'd']).max_gap(1)),
+        "'a'-'d' (missing 'b')"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent_iter::<char, _>(['a', 'c', 'd'].iter()).max_gap(1)
+        ),
+        "'a'-'d' (missing 'b')"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent(&[u128::MAX - 2, u128::MAX]).max_gap(1)
+        ),
+        "340282366920938463463374607431768211453-340282366920938463463374607431768211455 \
+         (missing 340282366920938463463374607431768211454)"
+    );
+
+    // Repeats and strides take precedence over gaps.
+    assert_eq!(
+        show(
+            debug_adjacent(&[1u32, 1, 1, 3, 4])
+                .max_gap(1)
+                .repeats(Repeats::WithRanges)
+        ),
+        "1 x3, 3-4"
+    );
+    assert_eq!(
+        show(
+            debug_adjacent(&[0u32, 4, 8, 9, 11])
+                .max_gap(1)
+                .stride(Stride::Fixed(4))
+        ),
+        "0-8/4, 9-11 (missing 10)"
+    );
+
+    // The surrogates are a gap of 0x800 code points.
+    let chars = ['\u{d7fe}', '\u{e000}', '\u{e001}'];
+    assert_eq!(
+        format!("{:?}", debug_adjacent(&chars).max_gap(1)),
+        "'\\u{d7fe}', '\\u{e000}'-'\\u{e001}'"
+    );
+
+    // The closure form cannot measure distances, so it never finds gaps.
+    let by = debug_adjacent_by(&blocks, |a, b| a + 1 == *b).max_gap(1);
+    assert_eq!(format!("{:?}", by), "100-102, 104-106, 108-110, 115-116");
+}
+
+#[test]
+fn test_sequential() {
+    #[derive(Copy, Clone, Debug)]
+    struct BlockNumber(u64);
+
+    impl IsAdjacent for BlockNumber {
+        fn successor(&self) -> Option<Self> {
+            self.0.checked_add(1).map(BlockNumber)
+        }
+
+        fn predecessor(&self) -> Option<Self> {
+            self.0.checked_sub(1).map(BlockNumber)
+        }
+
+        fn distance(&self, other: &Self) -> Option<u128> {
+            other.0.checked_sub(self.0).map(u128::from)
+        }
+    }
+
+    impl Sequential for BlockNumber {}
+
+    // The methods are only on `IsAdjacent`, so they can be called with both traits in scope.
+    assert_eq!(5u32.successor(), Some(6));
+    assert_eq!('b'.predecessor(), Some('a'));
+    assert_eq!(3i8.distance(&5), Some(2));
+
+    let blocks = [1, 2, 3, 5, 6, 6, 6, 20, 16, 12].map(BlockNumber);
+    assert!(BlockNumber(4).is_adjacent(&BlockNumber(5)));
+    assert!(!BlockNumber(5).is_adjacent(&BlockNumber(4)));
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent(&blocks).notation(RangeNotation::HalfOpen)
+        ),
+        "BlockNumber(1)..BlockNumber(4), BlockNumber(5)..BlockNumber(7), BlockNumber(6), \
+         BlockNumber(6), BlockNumber(20), BlockNumber(16), BlockNumber(12)"
+    );
+
+    let numbers = |dump: DebugAdjacent<BlockNumber>| {
+        format!("{:?}", dump.fmt_item(|b, f| write!(f, "{}", b.0)))
+    };
+    assert_eq!(
+        numbers(debug_adjacent(&blocks).max_gap(1)),
+        "1-6 (missing 4), 6, 6, 20, 16, 12"
+    );
+    assert_eq!(
+        numbers(
+            debug_adjacent(&blocks)
+                .repeats(Repeats::WithRanges)
+                .stride(Stride::Infer)
+                .direction(Direction::Both)
+        ),
+        "1-3, 5-6, 6 x2, 20-12/4"
+    );
+
+    // Types that implement `Sequential` can still be used with the closure form.
+    let by = debug_adjacent_by(&blocks, |a, b| a.0 / 10 == b.0 / 10);
+    assert_eq!(
+        format!("{:?}", by.fmt_item(|b, f| write!(f, "{}", b.0))),
+        "1-6, 20, 16-12"
+    );
+}
+
+#[test]
+fn test_debug_gaps() {
+    let items = [10u32, 12, 13, 14, 15, 20];
+    assert_eq!(format!("{:?}", debug_gaps(&items)), "11, 16-19");
+    assert_eq!(
+        format!("{:?}", debug_gaps(&items).within(0..=24)),
+        "0-9, 11, 16-19, 21-24"
+    );
+    assert_eq!(format!("{:?}", debug_gaps(&items).within(12..=17)), "16-17");
+    assert_eq!(format!("{:?}", debug_gaps(&items).within(5..=7)), "5-7");
+    assert_eq!(format!("{:?}", debug_gaps(&items).within(30..=32)), "30-32");
+    assert_eq!(
+        format!("{:?}", debug_gaps(&items).within(RangeInclusive::new(7, 5))),
+        ""
+    );
+
+    // Nothing is missing.
+    assert_eq!(format!("{:?}", debug_gaps(&[1u8, 2, 3])), "");
+    assert_eq!(format!("{:?}", debug_gaps::<u8>(&[])), "");
+    assert_eq!(format!("{:?}", debug_gaps::<u8>(&[]).within(1..=3)), "1-3");
+
+    // Repeated and out-of-order items are ignored.
+    assert_eq!(
+        format!("{:?}", debug_gaps(&[1i8, 1, 4, 2, 4, 7])),
+        "2-3, 5-6"
+    );
+
+    // Other types, and the ends of their ranges.
+    assert_eq!(format!("{:?}", debug_gaps(&[-3i64, 3])), "-2..=2");
+    assert_eq!(
+        format!("{:?}", debug_gaps(&[u8::MAX]).within(250..=u8::MAX)),
+        "250-254"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_gaps(&[i128::MIN, i128::MAX]).within(i128::MIN..=i128::MAX)
+        ),
+        "-170141183460469231731687303715884105727..=170141183460469231731687303715884105726"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_gaps::<u128>(&[]).within(0..=u128::MAX).header(true)
+        ),
+        format!("1 run: 0-{}", u128::MAX)
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_gaps::<u128>(&[7])
+                .within(0..=1 << 100)
+                .header(true)
+                .counts(true)
+        ),
+        "1267650600228229401496703205376 items in 2 runs: 0-6 (7), \
+         8-1267650600228229401496703205376 (1267650600228229401496703205369)"
+    );
+    assert_eq!(
+        format!("{:?}", debug_gaps(&['a', 'c', 'f'])),
+        "'b', 'd'-'e'"
+    );
+
+    // Options
+    assert_eq!(
+        format!("{:?}", debug_gaps(&items).notation(RangeNotation::HalfOpen)),
+        "11, 16..20"
+    );
+    assert_eq!(
+        format!("{:?}", debug_gaps(&items).min_run(5)),
+        "11, 16, 17, 18, 19"
+    );
+    assert_eq!(
+        format!("{:?}", debug_gaps(&items).counts(true).header(true)),
+        "5 items in 2 runs: 11, 16-19 (4)"
+    );
+    assert_eq!(
+        format!("{:x?}", debug_gaps(&items).within(0..=31)),
+        "0-9, b, 10-13, 15-1f"
+    );
+    assert_eq!(format!("{:?}", debug_gaps(&items).sep("..")), "11, 16..19");
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_gaps(&[-9i32, -1]).negatives(NegativeStyle::Parenthesize)
+        ),
+        "(-8)-(-2)"
+    );
+    assert_eq!(
+        format!("{:?}", debug_gaps(&items).within(0..=24).max_runs(2)),
+        "0-9, ... 2 more runs (5 items) ..., 21-24"
+    );
+    assert_eq!(
+        format!("{:#?}", debug_gaps(&items).pretty(Pretty::Off)),
+        "11, 16-19"
+    );
+    assert_eq!(
+        format!("{:#?}", debug_gaps(&items)),
+        "[\n    11,\n    16-19,\n]"
+    );
+    assert_eq!(
+        format!("{:?}", debug_gaps(&items).stats(true)),
+        "11, 16-19; RunStats { items: 5, runs: 2, longest: 4, shortest: 1, mean_len: 2.5, \
+         largest_gap: Some(4), fragmentation: 0.4 }"
+    );
+}
+
+#[test]
+fn test_dump_ranges_shortest() {
+    use std::vec::Vec;
+
+    let bitmap: Vec<u32> = (0..4096).filter(|&n| n % 1000 != 17).collect();
+    assert_eq!(
+        format!("{:?}", debug_adjacent(&bitmap).shortest(true)),
+        "all of 0-4095 except 17, 1017, 2017, 3017, 4017"
+    );
+    assert_eq!(
+        format!("{:?}", debug_adjacent(&bitmap)),
+        "0-16, 18-1016, 1018-2016, 2018-3016, 3018-4016, 4018-4095"
+    );
+
+    // The runs are shorter, or as short.
+    let items = [0u32, 1, 2, 3, 10, 11, 12];
+    assert_eq!(
+        format!("{:?}", debug_adjacent(&items).shortest(true)),
+        "0-3, 10-12"
+    );
+    assert_eq!(format!("{:?}", debug_adjacent(&[5u8]).shortest(true)), "5");
+    assert_eq!(
+        format!("{:?}", debug_adjacent::<u8>(&[]).shortest(true)),
+        ""
+    );
+
+    // The strided form.
+    let items = [0u32, 4, 8, 12, 16, 20];
+    assert_eq!(
+        format!("{:?}", debug_adjacent(&items).shortest(true)),
+        "0-20/4"
+    );
+
+    // The complement is not used for items that are not strictly increasing.
+    let items = [9u32, 7, 5, 3, 1, 0];
+    let dump = debug_adjacent(&items).direction(Direction::Both);
+    assert_eq!(format!("{:?}", dump.shortest(true)), "9-1/2, 0");
+    let mut items = bitmap.clone();
+    items.insert(1, 1);
+    assert!(format!("{:?}", debug_adjacent(&items).shortest(true)).starts_with("0-1, 1-16"));
+
+    // The other options apply to the complement, except for the header.
+    let dump = debug_adjacent(&bitmap)
+        .shortest(true)
+        .notation(RangeNotation::Inclusive)
+        .header(true)
+        .min_run(3);
+    assert_eq!(
+        format!("{:?}", dump),
+        "all of 0..=4095 except 17, 1017, 2017, 3017, 4017"
+    );
+    assert_eq!(
+        format!("{}", display_adjacent(&bitmap).shortest(true)),
+        "all of 0-4095 except 17, 1017, 2017, 3017, 4017"
+    );
+    assert_eq!(
+        format!("{:>50?}|", debug_adjacent(&bitmap).shortest(true)),
+        "   all of 0-4095 except 17, 1017, 2017, 3017, 4017|"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent(&bitmap)
+                .shortest(true)
+                .fmt_item(|n, f| write!(f, "#{}", n))
+        ),
+        "all of #0-#4095 except #17, #1017, #2017, #3017, #4017"
+    );
+
+    // Nothing changes for adjacency that is given by a function.
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent_by(&bitmap, |a, b| a + 1 == *b).shortest(true)
+        ),
+        "0-16, 18-1016, 1018-2016, 2018-3016, 3018-4016, 4018-4095"
+    );
+}
+
+#[test]
+fn test_run_stats() {
+    let blocks = [42u32, 100, 101, 102, 103, 104, 20, 31, 32, 33, 34];
+    let stats = run_stats(&blocks);
+    assert_eq!(
+        stats,
+        RunStats {
+            items: 11,
+            runs: 4,
+            longest: 5,
+            shortest: 1,
+            largest_gap: Some(83),
+        }
+    );
+    assert_eq!(stats.mean_len(), 2.75);
+    assert_eq!(
+        format!("{:?}", run_stats(&[1u8, 2, 3, 7])),
+        "RunStats { items: 4, runs: 2, longest: 3, shortest: 1, mean_len: 2.0, \
+         largest_gap: Some(3), fragmentation: 0.5 }"
+    );
+
+    // Without distances, or without items.
+    let stats = run_stats_by(&blocks, |a, b| a + 1 == *b);
+    assert_eq!((stats.runs, stats.largest_gap), (4, None));
+    let stats = run_stats::<u32>(&[]);
+    assert_eq!(stats, RunStats::default());
+    assert_eq!((stats.mean_len(), stats.fragmentation()), (0.0, 0.0));
+    let stats = run_stats(&[5u64, 6]);
+    assert_eq!((stats.shortest, stats.largest_gap), (2, None));
+    assert_eq!(run_stats(&[3i8, 3]).largest_gap, Some(0));
+
+    // Appended to the output of the wrapper types.
+    let items = [1u8, 2, 3, 7];
+    let expected = "1-3, 7; RunStats { items: 4, runs: 2, longest: 3, shortest: 1, \
+                    mean_len: 2.0, largest_gap: Some(3), fragmentation: 0.5 }";
+    assert_eq!(
+        format!("{:?}", debug_adjacent(&items).stats(true)),
+        expected
+    );
+    assert_eq!(
+        format!("{}", display_adjacent(&items).stats(true)),
+        expected
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent_iter(items.iter().copied()).stats(true)
+        ),
+        expected
+    );
+    assert_eq!(
+        format!("{:?}", debug_adjacent(&items).stats(true).shortest(true)),
+        expected
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent_by(&items, |a, b| a + 1 == *b).stats(true)
+        ),
+        "1-3, 7; RunStats { items: 4, runs: 2, longest: 3, shortest: 1, \
+         mean_len: 2.0, largest_gap: None, fragmentation: 0.5 }"
+    );
+
+    // The statistics describe the runs found with the options, including elided runs.
+    let dump = debug_adjacent(&items)
+        .stats(true)
+        .min_run(4)
+        .head(1)
+        .tail(0);
+    assert_eq!(
+        format!("{:?}", dump),
+        "1, ... 3 more runs (3 items) ...; RunStats { items: 4, runs: 4, longest: 1, \
+         shortest: 1, mean_len: 1.0, largest_gap: Some(3), fragmentation: 1.0 }"
+    );
+}
@@ -0,0 +1,365 @@
+//! Writes maps with integer keys, collapsing runs of adjacent keys that map to equal values.
+
+use crate::runs::Span;
+use crate::write::{fmt_inline, fmt_prefixed_range, fmt_runs, Steps};
+use crate::{DefaultFormatter, IsAdjacent, Options};
+use core::fmt::{Debug, Formatter};
+
+/// Returns a value that implements `Debug` by collapsing runs of adjacent keys that map to equal
+/// values, such as the entries of a page table or an interrupt routing table.
+///
+/// `entries` can be anything that can be iterated and cloned and that produces [`MapEntry`]
+/// values, such as `&[(u64, V)]`, a `&BTreeMap<u64, V>` or a `BTreeMap::range`. The `IsAdjacent`
+/// trait defines whether two keys are adjacent, and `PartialEq` whether two values are equal.
+/// The entries are not sorted, so keys must already be in increasing order to be collapsed.
+///
+/// # Example
+/// ```
+/// use dbg_ranges::debug_adjacent_map;
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Page {
+///     Unmapped,
+///     Rw(u64),
+///     Ro,
+/// }
+///
+/// let table = [
+///     (0u64, Page::Unmapped),
+///     (1, Page::Unmapped),
+///     (2, Page::Rw(0x4000)),
+///     (3, Page::Rw(0x4000)),
+///     (4, Page::Rw(0x4000)),
+///     (5, Page::Ro),
+///     (9, Page::Ro),
+/// ];
+/// assert_eq!(
+///     format!("{:x?}", debug_adjacent_map(&table)),
+///     "0-1 => Unmapped, 2-4 => Rw(4000), 5 => Ro, 9 => Ro"
+/// );
+/// ```
+pub fn debug_adjacent_map<I>(entries: I) -> DebugAdjacentMap<'static, I, ValuesEq<I>>
+where
+    I: IntoIterator + Clone,
+    I::IntoIter: Clone,
+    I::Item: MapEntry,
+    MapKey<I>: Debug + IsAdjacent,
+    MapValue<I>: Debug + PartialEq,
+{
+    DebugAdjacentMap::new(entries, <MapValue<I> as PartialEq>::eq)
+}
+
+/// Returns a value that implements `Debug` by collapsing runs of adjacent keys that map to equal
+/// values.
+///
+/// The `values_eq` parameter defines whether two values are equal. This is useful for values that
+/// do not implement `PartialEq`, or for values that should be grouped by only some of their
+/// fields. Otherwise this is the same as [`debug_adjacent_map`].
+///
+/// # Example
+/// ```
+/// use dbg_ranges::debug_adjacent_map_by;
+/// use std::collections::BTreeMap;
+///
+/// let irqs = BTreeMap::from([(32u32, (0, 'a')), (33, (0, 'b')), (34, (1, 'c'))]);
+/// assert_eq!(
+///     format!("{:?}", debug_adjacent_map_by(&irqs, |a, b| a.0 == b.0)),
+///     "32-33 => (0, 'a'), 34 => (1, 'c')"
+/// );
+/// ```
+pub fn debug_adjacent_map_by<I, E>(entries: I, values_eq: E) -> DebugAdjacentMap<'static, I, E>
+where
+    I: IntoIterator + Clone,
+    I::IntoIter: Clone,
+    I::Item: MapEntry,
+    MapKey<I>: Debug + IsAdjacent,
+    MapValue<I>: Debug,
+    E: Fn(&MapValue<I>, &MapValue<I>) -> bool,
+{
+    DebugAdjacentMap::new(entries, values_eq)
+}
+
+/// The entries of a map, which can be displayed by [`DebugAdjacentMap`].
+///
+/// Implementations are provided for `&(K, V)`, which is produced by iterating a slice of pairs,
+/// and for `(&K, &V)`, which is produced by iterating a `BTreeMap` by reference. Both give access
+/// to the key itself, rather than a reference to it, so that the key's
+/// [`IsAdjacent::successor`] can be used.
+pub trait MapEntry {
+    /// The type of the key, which is tested for adjacency
+    type Key;
+
+    /// The type of the value, which is tested for equality
+    type Value;
+
+    /// Returns the key of the entry.
+    fn key(&self) -> &Self::Key;
+
+    /// Returns the value of the entry.
+    fn value(&self) -> &Self::Value;
+}
+
+impl<'e, K, V> MapEntry for (&'e K, &'e V) {
+    type Key = K;
+    type Value = V;
+
+    fn key(&self) -> &K {
+        self.0
+    }
+
+    fn value(&self) -> &V {
+        self.1
+    }
+}
+
+impl<'e, K, V> MapEntry for &'e (K, V) {
+    type Key = K;
+    type Value = V;
+
+    fn key(&self) -> &K {
+        &self.0
+    }
+
+    fn value(&self) -> &V {
+        &self.1
+    }
+}
+
+/// The key type of the entries produced by `I`.
+pub type MapKey<I> = <<I as IntoIterator>::Item as MapEntry>::Key;
+
+/// The value type of the entries produced by `I`.
+pub type MapValue<I> = <<I as IntoIterator>::Item as MapEntry>::Value;
+
+/// The `PartialEq` function that [`debug_adjacent_map`] uses to compare the values produced by `I`.
+pub type ValuesEq<I> = fn(&MapValue<I>, &MapValue<I>) -> bool;
+
+/// Displays the entries of a map, collapsing runs of adjacent keys that map to equal values into
+/// `start-end => value` notation.
+///
+/// The entries are cloned each time this value is formatted. Use [`debug_adjacent_map`] or
+/// [`debug_adjacent_map_by`] to create this type.
+#[derive(Copy, Clone)]
+pub struct DebugAdjacentMap<'a, I, E> {
+    /// The entries that will be displayed
+    pub entries: I,
+
+    /// Controls how the runs are written. Each run of keys is written as a range, and the entries
+    /// in it are its items. The runs are found by comparing both keys and values, so only the
+    /// options that apply to the whole list and to the way ranges are written are used.
+    pub options: Options<'a>,
+
+    /// The function that tests whether two values are equal
+    pub values_eq: E,
+}
+
+impl<'a, I, E> DebugAdjacentMap<'a, I, E> {
+    /// Constructor
+    pub fn new(entries: I, values_eq: E) -> Self {
+        Self {
+            entries,
+            options: Options::default(),
+            values_eq,
+        }
+    }
+
+    option_setters!(list 'a);
+    option_setters!(range 'a);
+}
+
+impl<'a, I, E> Debug for DebugAdjacentMap<'a, I, E>
+where
+    I: IntoIterator + Clone,
+    I::IntoIter: Clone,
+    I::Item: MapEntry,
+    MapKey<I>: Debug + IsAdjacent,
+    MapValue<I>: Debug,
+    E: Fn(&MapValue<I>, &MapValue<I>) -> bool,
+{
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        let runs = Groups {
+            entries: self.entries.clone().into_iter(),
+            values_eq: &self.values_eq,
+        };
+        fmt_runs(
+            f,
+            runs,
+            &self.options,
+            &DefaultFormatter,
+            Steps::none(),
+            |group: &Group<I::Item>, f: &mut Formatter| {
+                let last = group.last.as_ref().map(|last| last.key());
+                fmt_prefixed_range(f, "", group.first.key(), last, group.len, &self.options)?;
+                f.write_str(" => ")?;
+                fmt_inline(f, group.first.value())
+            },
+        )
+    }
+}
+
+/// A run of adjacent keys that map to equal values, found by [`Groups`].
+struct Group<X> {
+    /// The first entry in the run
+    first: X,
+
+    /// The last entry in the run, or `None` if the run contains only `first`
+    last: Option<X>,
+
+    /// The number of entries in the run
+    len: u128,
+}
+
+/// Iterates the runs of adjacent keys that map to equal values. Each one is returned as a run of
+/// one item, whose length is the number of entries in it.
+struct Groups<'e, I, E> {
+    /// The entries that have not yet been visited
+    entries: I,
+
+    /// The function that tests whether two values are equal
+    values_eq: &'e E,
+}
+
+impl<'e, I: Clone, E> Clone for Groups<'e, I, E> {
+    fn clone(&self) -> Self {
+        Self {
+            entries: self.entries.clone(),
+            values_eq: self.values_eq,
+        }
+    }
+}
+
+impl<'e, I, E> Iterator for Groups<'e, I, E>
+where
+    I: Iterator + Clone,
+    I::Item: MapEntry,
+    <I::Item as MapEntry>::Key: IsAdjacent,
+    E: Fn(&<I::Item as MapEntry>::Value, &<I::Item as MapEntry>::Value) -> bool,
+{
+    type Item = Span<Group<I::Item>, core::iter::Empty<Group<I::Item>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let first = self.entries.next()?;
+        let mut last: Option<I::Item> = None;
+        let mut len: u128 = 1;
+
+        while let Some(next) = self.entries.clone().next() {
+            let prev = last.as_ref().unwrap_or(&first);
+            if !(prev.key().is_adjacent(next.key()) && (self.values_eq)(prev.value(), next.value()))
+            {
+                break;
+            }
+            self.entries.next();
+            last = Some(next);
+            len += 1;
+        }
+
+        Some(Span {
+            first: Group { first, last, len },
+            last: None,
+            len,
+            descending: false,
+            step: None,
+            missing: 0,
+            items: core::iter::empty(),
+        })
+    }
+}
+
+#[test]
+fn test_debug_adjacent_map() {
+    use crate::RangeNotation;
+    use std::collections::BTreeMap;
+
+    let empty: [(u64, u8); 0] = [];
+    assert_eq!(format!("{:?}", debug_adjacent_map(&empty)), "");
+    assert_eq!(
+        format!("{:?}", debug_adjacent_map(&[(7u8, "a")])),
+        "7 => \"a\""
+    );
+
+    // A run ends when the keys are not adjacent or the values are not equal.
+    let entries = [
+        (0u32, 'x'),
+        (1, 'x'),
+        (2, 'y'),
+        (3, 'y'),
+        (5, 'y'),
+        (6, 'x'),
+        (6, 'x'),
+    ];
+    assert_eq!(
+        format!("{:?}", debug_adjacent_map(&entries)),
+        "0-1 => 'x', 2-3 => 'y', 5 => 'y', 6 => 'x', 6 => 'x'"
+    );
+
+    // Maps, and ranges of maps, iterated by reference
+    let mut table = BTreeMap::new();
+    table.extend((0u64..100).map(|page| (page, None)));
+    table.extend((100u64..164).map(|page| (page, Some(0x4000u64))));
+    table.insert(164, Some(0x9000));
+    assert_eq!(
+        format!("{:x?}", debug_adjacent_map(&table)),
+        "0-63 => None, 64-a3 => Some(4000), a4 => Some(9000)"
+    );
+    assert_eq!(
+        format!("{:?}", debug_adjacent_map(table.range(98..101))),
+        "98-99 => None, 100 => Some(16384)"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent_map_by(&table, |a, b| a.is_some() == b.is_some())
+        ),
+        "0-99 => None, 100-164 => Some(16384)"
+    );
+
+    // Options apply to the keys, and to the runs, which count the entries in them as items.
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent_map(&table)
+                .notation(RangeNotation::HalfOpen)
+                .header(true)
+        ),
+        "165 items in 3 runs: 0..100 => None, 100..164 => Some(16384), 164 => Some(36864)"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_adjacent_map(&entries).notation(RangeNotation::HalfOpen)
+        ),
+        "0..2 => 'x', 2..4 => 'y', 5 => 'y', 6 => 'x', 6 => 'x'"
+    );
+    assert_eq!(
+        format!("{:#?}", debug_adjacent_map(&entries[..3])),
+        "[\n    0-1 => 'x',\n    2 => 'y',\n]"
+    );
+
+    // Values are written on one line, with the caller's other flags.
+    #[derive(Debug, PartialEq)]
+    enum Page {
+        Rw(u64),
+        Ro,
+    }
+
+    let pages = [
+        (2u64, Page::Rw(0x4000)),
+        (3, Page::Rw(0x4000)),
+        (4, Page::Ro),
+    ];
+    assert_eq!(
+        format!(
+            "{:#x?}",
+            debug_adjacent_map(&pages).pretty(crate::Pretty::Off)
+        ),
+        "0x2-0x3 => Rw(4000), 0x4 => Ro"
+    );
+    assert_eq!(
+        format!("{:#x?}", debug_adjacent_map(&pages)),
+        "[\n    0x2-0x3 => Rw(4000),\n    0x4 => Ro,\n]"
+    );
+    assert_eq!(
+        format!("{:X?}", debug_adjacent_map(&pages)),
+        "2-3 => Rw(4000), 4 => Ro"
+    );
+}
@@ -0,0 +1,301 @@
+//! Writes lists of ranges, merging the ranges that touch or overlap.
+
+use crate::runs::Span;
+use crate::write::{fmt_runs, Steps};
+use crate::{DefaultFormatter, IsAdjacent, Options};
+use core::fmt::{Debug, Formatter};
+use core::ops::{Range, RangeInclusive};
+
+/// Returns a value that implements `Debug` by merging ranges that touch, such as the extents of
+/// a file.
+///
+/// A range is merged with the ranges before it if it starts where they end. Overlapping ranges
+/// are also merged if [`DebugRanges::merge_overlaps`] is set, and the merged range is then
+/// followed by `(overlap)`. Each range is written with its `Debug` implementation, so formatting
+/// flags such as `{:x?}` apply to its endpoints.
+///
+/// # Example
+/// ```
+/// use dbg_ranges::{debug_ranges, Pretty};
+///
+/// let extents = [0x1000u64..0x2000, 0x2000..0x5000, 0x8000..0x9000];
+/// assert_eq!(
+///     format!("{:#x?}", debug_ranges(&extents).pretty(Pretty::Off)),
+///     "0x1000..0x5000, 0x8000..0x9000"
+/// );
+///
+/// let extents = [0u64..=9, 10..=19, 15..=30, 40..=40];
+/// assert_eq!(format!("{:?}", debug_ranges(&extents)), "0..=19, 15..=30, 40..=40");
+/// assert_eq!(
+///     format!("{:?}", debug_ranges(&extents).merge_overlaps(true)),
+///     "0..=30 (overlap), 40..=40"
+/// );
+/// ```
+pub fn debug_ranges<R: MergeRange>(ranges: &[R]) -> DebugRanges<'_, R> {
+    DebugRanges::new(ranges)
+}
+
+/// Ranges that can be merged by [`DebugRanges`].
+///
+/// Implementations are provided for [`Range`] and for [`RangeInclusive`] of any type that
+/// implements [`IsAdjacent`].
+pub trait MergeRange: Debug + Clone {
+    /// Returns `true` if `next` starts immediately after the end of `self`.
+    fn touches(&self, next: &Self) -> bool;
+
+    /// Returns `true` if `self` and `next` have at least one value in common.
+    fn overlaps(&self, next: &Self) -> bool;
+
+    /// Returns the smallest range that contains both `self` and `next`.
+    fn merge(&self, next: &Self) -> Self;
+}
+
+impl<T: PartialOrd + Clone + Debug> MergeRange for Range<T> {
+    fn touches(&self, next: &Self) -> bool {
+        self.end == next.start
+    }
+
+    fn overlaps(&self, next: &Self) -> bool {
+        // An empty range has no values, so it overlaps nothing.
+        self.start < self.end
+            && next.start < next.end
+            && next.start < self.end
+            && self.start < next.end
+    }
+
+    fn merge(&self, next: &Self) -> Self {
+        min(&self.start, &next.start).clone()..max(&self.end, &next.end).clone()
+    }
+}
+
+impl<T: PartialOrd + Clone + Debug + IsAdjacent> MergeRange for RangeInclusive<T> {
+    fn touches(&self, next: &Self) -> bool {
+        self.end().is_adjacent(next.start())
+    }
+
+    fn overlaps(&self, next: &Self) -> bool {
+        // An empty range has no values, so it overlaps nothing.
+        self.start() <= self.end()
+            && next.start() <= next.end()
+            && next.start() <= self.end()
+            && self.start() <= next.end()
+    }
+
+    fn merge(&self, next: &Self) -> Self {
+        let start = min(self.start(), next.start()).clone();
+        start..=max(self.end(), next.end()).clone()
+    }
+}
+
+fn min<'t, T: PartialOrd>(a: &'t T, b: &'t T) -> &'t T {
+    if b < a {
+        b
+    } else {
+        a
+    }
+}
+
+fn max<'t, T: PartialOrd>(a: &'t T, b: &'t T) -> &'t T {
+    if b > a {
+        b
+    } else {
+        a
+    }
+}
+
+/// Displays a list of ranges, merging the ranges that touch, and optionally those that overlap.
+///
+/// Use [`debug_ranges`] to create this type.
+#[derive(Copy, Clone)]
+pub struct DebugRanges<'a, R> {
+    /// The ranges that will be displayed
+    pub ranges: &'a [R],
+
+    /// Controls how the list is written. Each merged range is a run, and the ranges that were
+    /// merged into it are its items. The ranges are written with their `Debug` implementation,
+    /// so only the options that apply to the whole list, such as the header, elision, layout and
+    /// statistics, are used.
+    pub options: Options<'a>,
+
+    /// If `true`, ranges that overlap are merged, and the merged range is marked
+    pub merge_overlaps: bool,
+}
+
+impl<'a, R> DebugRanges<'a, R> {
+    /// Constructor
+    pub fn new(ranges: &'a [R]) -> Self {
+        Self {
+            ranges,
+            options: Options::default(),
+            merge_overlaps: false,
+        }
+    }
+
+    /// Sets whether ranges that overlap are merged. A merged range that contains an overlap is
+    /// followed by `(overlap)`.
+    pub fn merge_overlaps(mut self, merge_overlaps: bool) -> Self {
+        self.merge_overlaps = merge_overlaps;
+        self
+    }
+
+    option_setters!(list 'a);
+}
+
+impl<'a, R: MergeRange> Debug for DebugRanges<'a, R> {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        let runs = MergedRanges {
+            ranges: self.ranges.iter(),
+            merge_overlaps: self.merge_overlaps,
+        };
+        fmt_runs(
+            f,
+            runs,
+            &self.options,
+            &DefaultFormatter,
+            Steps::none(),
+            |merged: &Merged<R>, f: &mut Formatter| {
+                merged.range.fmt(f)?;
+                if merged.overlap {
+                    f.write_str(" (overlap)")?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+/// A range found by [`MergedRanges`].
+struct Merged<R> {
+    /// The union of the ranges that were merged
+    range: R,
+
+    /// `true` if any of the ranges that were merged overlap
+    overlap: bool,
+}
+
+/// Iterates the merged ranges in a list of ranges. Each one is returned as a run of one item,
+/// whose length is the number of ranges that were merged.
+#[derive(Clone)]
+struct MergedRanges<'a, R> {
+    /// The ranges that have not yet been visited
+    ranges: core::slice::Iter<'a, R>,
+
+    /// If `true`, ranges that overlap are merged
+    merge_overlaps: bool,
+}
+
+impl<'a, R: MergeRange> Iterator for MergedRanges<'a, R> {
+    type Item = Span<Merged<R>, core::iter::Empty<Merged<R>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut range = self.ranges.next()?.clone();
+        let mut overlap = false;
+        let mut len: u128 = 1;
+
+        while let Some(next) = self.ranges.clone().next() {
+            if range.touches(next) {
+                range = range.merge(next);
+            } else if self.merge_overlaps && range.overlaps(next) {
+                range = range.merge(next);
+                overlap = true;
+            } else {
+                break;
+            }
+            self.ranges.next();
+            len += 1;
+        }
+
+        Some(Span {
+            first: Merged { range, overlap },
+            last: None,
+            len,
+            descending: false,
+            step: None,
+            missing: 0,
+            items: core::iter::empty(),
+        })
+    }
+}
+
+#[test]
+fn test_debug_ranges() {
+    let empty: [Range<u64>; 0] = [];
+    assert_eq!(format!("{:?}", debug_ranges(&empty)), "");
+    let single = core::slice::from_ref(&(3u8..5));
+    assert_eq!(format!("{:?}", debug_ranges(single)), "3..5");
+    assert_eq!(
+        format!("{:?}", debug_ranges(&[0u32..4, 4..8, 8..9, 10..12, 12..12])),
+        "0..9, 10..12"
+    );
+
+    // Ranges are only merged with the ranges before them.
+    assert_eq!(
+        format!("{:?}", debug_ranges(&[4i32..8, 0..4, -3..-1])),
+        "4..8, 0..4, -3..-1"
+    );
+    assert_eq!(
+        format!("{:x?}", debug_ranges(&[0x1000u64..0x2000, 0x2000..0x5000])),
+        "1000..5000"
+    );
+
+    // Overlaps, including ranges that overlap an earlier range but not the one before them
+    let ranges = [0u64..10, 2..4, 6..8, 10..11, 20..30, 25..26, 40..41];
+    assert_eq!(
+        format!("{:?}", debug_ranges(&ranges)),
+        "0..10, 2..4, 6..8, 10..11, 20..30, 25..26, 40..41"
+    );
+    assert_eq!(
+        format!("{:?}", debug_ranges(&ranges).merge_overlaps(true)),
+        "0..11 (overlap), 20..30 (overlap), 40..41"
+    );
+    assert_eq!(
+        format!("{:?}", debug_ranges(&[5u8..10, 0..6]).merge_overlaps(true)),
+        "0..10 (overlap)"
+    );
+
+    // Inclusive ranges touch if the start of one is adjacent to the end of the other.
+    assert_eq!(
+        format!("{:?}", debug_ranges(&[0u8..=9, 10..=255])),
+        "0..=255"
+    );
+    assert_eq!(
+        format!("{:?}", debug_ranges(&['a'..='c', 'd'..='d', 'f'..='g'])),
+        "'a'..='d', 'f'..='g'"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_ranges(&[0u8..=9, 9..=12, 14..=14]).merge_overlaps(true)
+        ),
+        "0..=12 (overlap), 14..=14"
+    );
+
+    // Empty ranges have no values in common with any range.
+    assert_eq!(
+        format!("{:?}", debug_ranges(&[0u32..10, 5..5]).merge_overlaps(true)),
+        "0..10, 5..5"
+    );
+    assert_eq!(
+        format!(
+            "{:?}",
+            debug_ranges(&[0u32..=10, RangeInclusive::new(5, 4)]).merge_overlaps(true)
+        ),
+        "0..=10, 5..=4"
+    );
+
+    // Options apply to the merged ranges, which count the ranges that were merged as items.
+    let ranges = [0u64..4, 4..8, 10..12, 12..14, 14..16, 20..21];
+    assert_eq!(
+        format!("{:?}", debug_ranges(&ranges).header(true).max_runs(2)),
+        "6 items in 3 runs: 0..8, ... 1 more run (3 items) ..., 20..21"
+    );
+    assert_eq!(
+        format!("{:#?}", debug_ranges(&ranges)),
+        "[\n    0..8,\n    10..16,\n    20..21,\n]"
+    );
+    assert_eq!(
+        format!("{:?}", debug_ranges(&ranges).stats(true)),
+        "0..8, 10..16, 20..21; RunStats { items: 6, runs: 3, longest: 3, shortest: 1, \
+         mean_len: 2.0, largest_gap: None, fragmentation: 0.5 }"
+    );
+}
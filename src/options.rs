@@ -0,0 +1,339 @@
+//! Settings that control how runs are written.
+
+/// Settings that control how runs are written. These are shared by all of the wrapper types in
+/// this crate.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Options<'a> {
+    /// How a range of two or more items is written.
+    pub notation: RangeNotation,
+
+    /// The separator between the first and last item in a range, when using
+    /// [`RangeNotation::Dash`].
+    pub sep: &'a str,
+
+    /// How ranges are written when the separator could be mistaken for the sign of a negative
+    /// endpoint.
+    pub negatives: NegativeStyle,
+
+    /// Which runs of adjacent items are collapsed into ranges.
+    pub direction: Direction,
+
+    /// Whether runs of items with a constant step other than 1, such as `0, 4, 8, 12`, are
+    /// collapsed into ranges.
+    pub stride: Stride,
+
+    /// Whether runs of repeated items, such as `0, 0, 0, 0`, are collapsed into `0 x4`.
+    pub repeats: Repeats,
+
+    /// The number of consecutive missing values that a run may skip, so that mostly contiguous
+    /// items are written as one range, e.g. `100-110 (missing 103, 107)`. The default is 0, which
+    /// allows no gaps.
+    ///
+    /// The distance between items is found with [`IsAdjacent::distance`], so this has no effect
+    /// on the wrapper types that test for adjacency with a function.
+    ///
+    /// [`IsAdjacent::distance`]: crate::IsAdjacent::distance
+    pub max_gap: usize,
+
+    /// How the values that are missing from a run with gaps are written.
+    pub gaps: GapStyle,
+
+    /// The minimum number of items in a range. Runs of adjacent items that are shorter than this
+    /// are written as individual items. The default is 2, which writes every run as a range.
+    pub min_run: usize,
+
+    /// If `true`, each range is followed by the number of items in it, e.g. `100-104 (5)`.
+    pub counts: bool,
+
+    /// If `true`, the output starts with the total number of items and runs, e.g.
+    /// `37 items in 5 runs: ...`.
+    pub header: bool,
+
+    /// If set, and there are more runs than `head` and `tail` together, only the first `head`
+    /// runs are written before a marker such as `... 1234 more runs (56789 items) ...`.
+    pub head: Option<usize>,
+
+    /// If set, and there are more runs than `head` and `tail` together, only the last `tail`
+    /// runs are written after the marker.
+    pub tail: Option<usize>,
+
+    /// The layout used when formatting with `{:#?}`.
+    pub pretty: Pretty,
+
+    /// If `true`, the items are written in whichever form is shortest: their runs, their runs
+    /// with [`Stride::Infer`], or the values that are missing between the first and last item,
+    /// e.g. `all of 0-4095 except 17, 900-901`. The last form is only used if the items are
+    /// strictly increasing, and is written without the header.
+    ///
+    /// This applies to the wrapper types that hold a slice and find runs with the
+    /// [`IsAdjacent`] trait. It has no effect on the others.
+    ///
+    /// # Example
+    /// ```
+    /// use dbg_ranges::debug_adjacent;
+    ///
+    /// let missing = [17, 900, 901, 1024, 2048, 3072];
+    /// let bitmap: Vec<u32> = (0..4096).filter(|n| !missing.contains(n)).collect();
+    /// assert_eq!(
+    ///     format!("{:?}", debug_adjacent(&bitmap).shortest(true)),
+    ///     "all of 0-4095 except 17, 900-901, 1024, 2048, 3072"
+    /// );
+    ///
+    /// let items = [0u32, 1, 2, 3, 10, 11, 12];
+    /// assert_eq!(format!("{:?}", debug_adjacent(&items).shortest(true)), "0-3, 10-12");
+    /// ```
+    ///
+    /// [`IsAdjacent`]: crate::IsAdjacent
+    pub shortest: bool,
+
+    /// If `true`, the list is followed by the [`RunStats`] of its runs, e.g.
+    /// `0-4, 10; RunStats { items: 6, runs: 2, ... }`. The statistics describe the runs as they
+    /// are found with these options, including those that are elided.
+    ///
+    /// [`RunStats`]: crate::RunStats
+    pub stats: bool,
+}
+
+impl<'a> Default for Options<'a> {
+    /// Returns options that use [`RangeNotation::Dash`] with a `-` separator,
+    /// [`NegativeStyle::Auto`], and collapse increasing runs of 2 or more adjacent items, without
+    /// counts or a header, which write every run, and which use [`Pretty::Lines`] for `{:#?}`.
+    fn default() -> Self {
+        Self {
+            notation: RangeNotation::Dash,
+            sep: "-",
+            negatives: NegativeStyle::Auto,
+            direction: Direction::Ascending,
+            stride: Stride::Off,
+            repeats: Repeats::Off,
+            max_gap: 0,
+            gaps: GapStyle::List,
+            min_run: 2,
+            counts: false,
+            header: false,
+            head: None,
+            tail: None,
+            pretty: Pretty::Lines,
+            shortest: false,
+            stats: false,
+        }
+    }
+}
+
+/// The notation used to write a range of two or more items. Single items are always written on
+/// their own.
+///
+/// # Example
+/// ```
+/// use dbg_ranges::{debug_adjacent, RangeNotation};
+///
+/// let items = [10u32, 12, 13, 14, 15];
+/// let show = |notation| format!("{:?}", debug_adjacent(&items).notation(notation));
+/// assert_eq!(show(RangeNotation::Dash), "10, 12-15");
+/// assert_eq!(show(RangeNotation::Inclusive), "10, 12..=15");
+/// assert_eq!(show(RangeNotation::HalfOpen), "10, 12..16");
+/// assert_eq!(show(RangeNotation::Interval), "10, [12, 15]");
+/// assert_eq!(show(RangeNotation::Colon), "10, 12:15");
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RangeNotation {
+    /// `12-15`, using [`Options::sep`] as the separator. This is the default.
+    #[default]
+    Dash,
+
+    /// `12..=15`, a Rust inclusive range.
+    Inclusive,
+
+    /// `12..16`, a Rust half-open range. The end is found with [`IsAdjacent::successor`]; if
+    /// there is no successor, the range is written as an inclusive range instead.
+    ///
+    /// [`IsAdjacent::successor`]: crate::IsAdjacent::successor
+    HalfOpen,
+
+    /// `[12, 15]`, a closed interval.
+    Interval,
+
+    /// `12:15`
+    Colon,
+}
+
+/// Controls how a range is written in [`RangeNotation::Dash`] when the separator contains `-` and
+/// one of its endpoints is negative. With the default separator, `[-5, -4, -3]` would otherwise
+/// be written as `-5--3`, which is hard to read and hard to search for.
+///
+/// Whether an endpoint is negative is decided by whether its output starts with `-`, so this
+/// applies to signed integers and to any other type whose output can start with a minus sign.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum NegativeStyle {
+    /// Writes the range using `..=` instead of the separator, e.g. `-5..=-3`. This is the default.
+    #[default]
+    Auto,
+
+    /// Writes negative endpoints in parentheses, e.g. `(-5)-(-3)`.
+    Parenthesize,
+
+    /// Always uses the separator, even if the output is ambiguous, e.g. `-5--3`.
+    Keep,
+}
+
+/// Selects which runs of adjacent items are collapsed into ranges.
+///
+/// A descending run is a run in which each item is adjacent to the item before it, such as a list
+/// of blocks that were allocated in reverse. It is written with its first item first, e.g.
+/// `20-17`.
+///
+/// # Example
+/// ```
+/// use dbg_ranges::{debug_adjacent, Direction};
+///
+/// let items = [20u32, 19, 18, 17, 5, 6, 7];
+/// let show = |direction| format!("{:?}", debug_adjacent(&items).direction(direction));
+/// assert_eq!(show(Direction::Ascending), "20, 19, 18, 17, 5-7");
+/// assert_eq!(show(Direction::Descending), "20-17, 5, 6, 7");
+/// assert_eq!(show(Direction::Both), "20-17, 5-7");
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Only increasing runs, in which each item is adjacent to the next. This is the default.
+    #[default]
+    Ascending,
+
+    /// Only decreasing runs, in which each item is adjacent to the one before it.
+    Descending,
+
+    /// Both increasing and decreasing runs. The direction of each run is chosen by its first two
+    /// items; if they are adjacent in both directions, the run is increasing.
+    Both,
+}
+
+/// Selects whether runs of items with a constant step, such as every 4th page or even-numbered
+/// CPUs, are collapsed into ranges.
+///
+/// A strided run is written as a range followed by its step, e.g. `0-16/4`, or `0..=16 step 4`
+/// when the range is written in Rust notation. Strided runs need at least 3 items, since two items
+/// are always a constant distance apart, and runs of adjacent items are still collapsed as usual.
+///
+/// The distance between items is found with [`IsAdjacent::distance`], which is implemented for
+/// the Rust integer types. This has no effect on the wrapper types that test for adjacency with a
+/// function.
+///
+/// # Example
+/// ```
+/// use dbg_ranges::{debug_adjacent, Stride};
+///
+/// let pages = [0u32, 4, 8, 12, 16, 3, 20, 21, 22];
+/// let show = |stride| format!("{:?}", debug_adjacent(&pages).stride(stride));
+/// assert_eq!(show(Stride::Off), "0, 4, 8, 12, 16, 3, 20-22");
+/// assert_eq!(show(Stride::Fixed(4)), "0-16/4, 3, 20-22");
+/// assert_eq!(show(Stride::Infer), "0-16/4, 3, 20-22");
+/// ```
+///
+/// [`IsAdjacent::distance`]: crate::IsAdjacent::distance
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Stride {
+    /// Only runs of adjacent items are collapsed. This is the default.
+    #[default]
+    Off,
+
+    /// Runs whose items are the given distance apart are also collapsed. A distance of 0 is the
+    /// same as `Off`, since repeated items are collapsed by [`Repeats`] instead.
+    Fixed(u128),
+
+    /// Runs whose items are any constant distance apart are also collapsed. The step of each run
+    /// is chosen by its first two items.
+    Infer,
+}
+
+/// Selects whether runs of repeated items are collapsed, which is useful for lists that hold
+/// states or counts rather than distinct values.
+///
+/// A run of repeated items is written as the item followed by the number of times that it is
+/// repeated, e.g. `0 x4`. Whether two items are repeats is decided by [`IsAdjacent::is_repeat`],
+/// which is implemented with `==` for the Rust integer types and `char`, or by the function given
+/// to [`DebugAdjacentBy::repeats_by`].
+///
+/// # Example
+/// ```
+/// use dbg_ranges::{debug_adjacent, Repeats};
+///
+/// let items = [5u32, 5, 5, 6, 7, 8];
+/// let show = |repeats| format!("{:?}", debug_adjacent(&items).repeats(repeats));
+/// assert_eq!(show(Repeats::Off), "5, 5, 5-8");
+/// assert_eq!(show(Repeats::Only), "5 x3, 6, 7, 8");
+/// assert_eq!(show(Repeats::WithRanges), "5 x3, 6-8");
+/// ```
+///
+/// [`IsAdjacent::is_repeat`]: crate::IsAdjacent::is_repeat
+/// [`DebugAdjacentBy::repeats_by`]: crate::DebugAdjacentBy::repeats_by
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Repeats {
+    /// Repeated items are written individually. This is the default.
+    #[default]
+    Off,
+
+    /// Runs of repeated items are collapsed, and other items are written individually.
+    Only,
+
+    /// Runs of repeated items are collapsed, and so are runs of adjacent items.
+    WithRanges,
+}
+
+/// How the values that are missing from a run are written, when [`Options::max_gap`] allows runs
+/// to have gaps.
+///
+/// # Example
+/// ```
+/// use dbg_ranges::{debug_adjacent, GapStyle};
+///
+/// let blocks = [100u32, 101, 102, 104, 105, 106, 108, 109, 110, 120];
+/// let dump = debug_adjacent(&blocks).max_gap(1);
+/// assert_eq!(format!("{:?}", dump), "100-110 (missing 103, 107), 120");
+/// assert_eq!(format!("{:?}", dump.gaps(GapStyle::Count)), "100-110 (-2), 120");
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum GapStyle {
+    /// Lists the missing values, e.g. `100-110 (missing 103, 107)`. This is the default. If the
+    /// type cannot compute [`IsAdjacent::successor`], the number of missing values is written
+    /// instead.
+    ///
+    /// [`IsAdjacent::successor`]: crate::IsAdjacent::successor
+    #[default]
+    List,
+
+    /// Writes the number of missing values, e.g. `100-110 (-2)`.
+    Count,
+}
+
+/// The layout of a list that is formatted with `{:#?}`.
+///
+/// The multi-line layouts put the list in brackets and indent it in the same way as `debug_list`,
+/// so that it nests correctly inside other values that are pretty-printed.
+///
+/// # Example
+/// ```
+/// use dbg_ranges::{debug_adjacent, Pretty};
+///
+/// let items = [1u32, 2, 3, 5, 7, 8];
+/// assert_eq!(format!("{:#?}", debug_adjacent(&items)), "[\n    1-3,\n    5,\n    7-8,\n]");
+/// assert_eq!(
+///     format!("{:#?}", debug_adjacent(&items).pretty(Pretty::Wrap(12))),
+///     "[\n    1-3, 5,\n    7-8,\n]"
+/// );
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Pretty {
+    /// One run on each line. This is the default.
+    #[default]
+    Lines,
+
+    /// As many runs on each line as fit within the given number of characters, including the
+    /// indentation within the list but not the indentation of any enclosing values.
+    ///
+    /// Runs are measured by writing them with the caller's flags, so lines written with `{:#x?}`
+    /// are wrapped by the length of their hexadecimal items.
+    Wrap(usize),
+
+    /// Stays on one line, as with `{:?}`. This is useful with `{:#x?}`, which also sets the flag
+    /// that selects the multi-line layout.
+    Off,
+}
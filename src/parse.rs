@@ -0,0 +1,541 @@
+//! Parses the output of the wrapper types back into items.
+//!
+//! This is the inverse of [`DebugAdjacent`](crate::DebugAdjacent): it reads a list such as
+//! `10, 12-15, 20` and returns the ranges that it describes. Every [`RangeNotation`] is accepted,
+//! as are the styles selected by [`NegativeStyle`], and the notation may differ from one range to
+//! the next.
+//!
+//! [`RangeNotation`]: crate::RangeNotation
+//! [`NegativeStyle`]: crate::NegativeStyle
+
+use crate::IsAdjacent;
+use core::fmt::{Display, Formatter};
+use core::marker::PhantomData;
+use core::ops::RangeInclusive;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// Returns an iterator over the ranges described by `text`.
+///
+/// Single items are returned as ranges that contain only that item. Parsing stops at the first
+/// error, which is returned as the last item of the iterator.
+///
+/// # Example
+/// ```
+/// use dbg_ranges::parse_ranges;
+///
+/// let ranges: Result<Vec<_>, _> = parse_ranges::<u32>("10, 12-15, 20..=21").collect();
+/// assert_eq!(ranges.unwrap(), [10..=10, 12..=15, 20..=21]);
+/// ```
+pub fn parse_ranges<T>(text: &str) -> ParseRanges<'_, T>
+where
+    T: ParseItem + IsAdjacent + PartialOrd + Clone,
+{
+    ParseRanges::new(text)
+}
+
+/// Parses `text` and returns all of the items that it describes, in order.
+///
+/// # Example
+/// ```
+/// use dbg_ranges::parse_items;
+///
+/// assert_eq!(parse_items::<i32>("-3..=-1, 5").unwrap(), [-3, -2, -1, 5]);
+/// assert_eq!(parse_items::<i32>("1, x").unwrap_err().pos, 3);
+/// ```
+#[cfg(feature = "alloc")]
+pub fn parse_items<T>(text: &str) -> Result<Vec<T>, ParseError>
+where
+    T: ParseItem + IsAdjacent + PartialOrd + Clone,
+{
+    let mut items = Vec::new();
+    let mut ranges = ParseRanges::<T>::new(text);
+
+    while let Some(range) = ranges.next() {
+        let (mut item, last) = range?.into_inner();
+        while item != last {
+            let next = item.successor().ok_or(ParseError {
+                kind: ParseErrorKind::CannotExpand,
+                pos: ranges.range_pos,
+            })?;
+            items.push(core::mem::replace(&mut item, next));
+        }
+        items.push(item);
+    }
+
+    Ok(items)
+}
+
+/// Items that can be parsed from the text that this crate writes for them.
+///
+/// Implementations are provided for Rust integer types, which accept decimal and `0x` hexadecimal
+/// digits, and for `char`, which accepts the quoted and escaped form written by `Debug`. Signed
+/// integers written in hexadecimal are read as two's complement, as `{:#x?}` writes them, so
+/// `0xfffffffb` is `-5i32`.
+pub trait ParseItem: Sized {
+    /// Parses an item from the start of `text`. Returns the item and the number of bytes that it
+    /// used, or `None` if `text` does not start with an item.
+    fn parse_item(text: &str) -> Option<(Self, usize)>;
+}
+
+/// Implements `ParseItem` for the integer type `$t`, whose hexadecimal digits are read as the
+/// unsigned type `$u` of the same size.
+macro_rules! int_parse {
+    ($t:ty, $u:ty) => {
+        impl ParseItem for $t {
+            fn parse_item(text: &str) -> Option<(Self, usize)> {
+                if let Some(hex) = text.strip_prefix("0x") {
+                    let len = hex
+                        .find(|c: char| !c.is_ascii_hexdigit())
+                        .unwrap_or(hex.len());
+                    let value = <$u>::from_str_radix(&hex[..len], 16).ok()? as $t;
+                    return Some((value, 2 + len));
+                }
+
+                let sign = usize::from(text.starts_with('-'));
+                let len = text[sign..]
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(text.len() - sign);
+                if len == 0 {
+                    return None;
+                }
+                let value = text[..sign + len].parse::<$t>().ok()?;
+                Some((value, sign + len))
+            }
+        }
+    };
+}
+int_parse!(u8, u8);
+int_parse!(u16, u16);
+int_parse!(u32, u32);
+int_parse!(u64, u64);
+int_parse!(u128, u128);
+int_parse!(usize, usize);
+int_parse!(i8, u8);
+int_parse!(i16, u16);
+int_parse!(i32, u32);
+int_parse!(i64, u64);
+int_parse!(i128, u128);
+int_parse!(isize, usize);
+
+impl ParseItem for char {
+    fn parse_item(text: &str) -> Option<(Self, usize)> {
+        let body = text.strip_prefix('\'')?;
+        let mut chars = body.chars();
+        let c = match chars.next()? {
+            '\\' => match chars.next()? {
+                '0' => '\0',
+                't' => '\t',
+                'r' => '\r',
+                'n' => '\n',
+                c @ ('\\' | '\'' | '"') => c,
+                'u' => {
+                    let hex = chars.as_str().strip_prefix('{')?;
+                    let len = hex.find('}')?;
+                    let c = char::from_u32(u32::from_str_radix(&hex[..len], 16).ok()?)?;
+                    chars = hex[len + 1..].chars();
+                    c
+                }
+                _ => return None,
+            },
+            c => c,
+        };
+        let rest = chars.as_str().strip_prefix('\'')?;
+        Some((c, text.len() - rest.len()))
+    }
+}
+
+/// An error found while parsing a list of ranges.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// What went wrong
+    pub kind: ParseErrorKind,
+
+    /// The byte offset within the input at which the error was found
+    pub pos: usize,
+}
+
+/// The kinds of error that can be found while parsing a list of ranges.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ParseErrorKind {
+    /// The input did not contain an item where one was needed.
+    ExpectedItem,
+
+    /// An item or range was followed by something other than a comma or the end of the input.
+    ExpectedComma,
+
+    /// A parenthesized item was not followed by `)`.
+    ExpectedCloseParen,
+
+    /// An interval was not separated by `,` or not followed by `]`.
+    ExpectedCloseBracket,
+
+    /// The last item in a range is before the first.
+    ReversedRange,
+
+    /// A half-open range does not contain any items, or its end has no predecessor.
+    EmptyRange,
+
+    /// The items within a range could not be listed, because the item type cannot compute a
+    /// successor.
+    CannotExpand,
+
+    /// A strided region of a `cpulist` was not written as `start-end:used/group`.
+    ExpectedSlash,
+
+    /// A strided region of a `cpulist` has a group size of zero, or uses more values than the
+    /// size of each group.
+    InvalidGroup,
+
+    /// A value in a `cpulist` does not fit in a `u32`, or is not less than the number of bits.
+    OutOfRange,
+}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        f.write_str(match self {
+            Self::ExpectedItem => "expected an item",
+            Self::ExpectedComma => "expected `,` or the end of the input",
+            Self::ExpectedCloseParen => "expected `)`",
+            Self::ExpectedCloseBracket => "expected `]`",
+            Self::ReversedRange => "the end of the range is before its start",
+            Self::EmptyRange => "the range is empty",
+            Self::CannotExpand => "the items in the range cannot be listed",
+            Self::ExpectedSlash => "expected `/`",
+            Self::InvalidGroup => {
+                "the group size is zero or smaller than the number of values used"
+            }
+            Self::OutOfRange => "the value is out of range",
+        })
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        write!(f, "{} at byte {}", self.kind, self.pos)
+    }
+}
+
+/// Iterates the ranges described by a string. Use [`parse_ranges`] to create this type.
+pub struct ParseRanges<'a, T> {
+    /// The input
+    text: &'a str,
+
+    /// The byte offset of the next character to parse
+    pos: usize,
+
+    /// The byte offset at which the most recent range started
+    range_pos: usize,
+
+    /// The separator used by ranges in [`RangeNotation::Dash`](crate::RangeNotation::Dash)
+    sep: &'a str,
+
+    /// `true` if the previous range was followed by a comma, so another range must follow
+    need_range: bool,
+
+    /// `true` once the input has been used up, or an error has been returned
+    done: bool,
+
+    _item: PhantomData<fn() -> T>,
+}
+
+impl<'a, T> ParseRanges<'a, T>
+where
+    T: ParseItem + IsAdjacent + PartialOrd + Clone,
+{
+    /// Constructor
+    pub fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            pos: 0,
+            range_pos: 0,
+            sep: "-",
+            need_range: false,
+            done: false,
+            _item: PhantomData,
+        }
+    }
+
+    /// Sets the separator that was used for ranges in
+    /// [`RangeNotation::Dash`](crate::RangeNotation::Dash). The default is `-`.
+    pub fn sep(mut self, sep: &'a str) -> Self {
+        self.sep = sep;
+        self
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            kind,
+            pos: self.pos,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Consumes `s` if the input continues with it.
+    fn eat(&mut self, s: &str) -> bool {
+        let found = !s.is_empty() && self.rest().starts_with(s);
+        if found {
+            self.pos += s.len();
+        }
+        found
+    }
+
+    fn expect(&mut self, s: &str, kind: ParseErrorKind) -> Result<(), ParseError> {
+        if self.eat(s) {
+            Ok(())
+        } else {
+            Err(self.error(kind))
+        }
+    }
+
+    fn parse_item(&mut self) -> Result<T, ParseError> {
+        let (item, len) =
+            T::parse_item(self.rest()).ok_or(self.error(ParseErrorKind::ExpectedItem))?;
+        self.pos += len;
+        Ok(item)
+    }
+
+    /// Parses an item that may be in parentheses, as written by
+    /// [`NegativeStyle::Parenthesize`](crate::NegativeStyle::Parenthesize).
+    fn parse_endpoint(&mut self) -> Result<T, ParseError> {
+        if self.eat("(") {
+            let item = self.parse_item()?;
+            self.expect(")", ParseErrorKind::ExpectedCloseParen)?;
+            Ok(item)
+        } else {
+            self.parse_item()
+        }
+    }
+
+    fn parse_range(&mut self) -> Result<RangeInclusive<T>, ParseError> {
+        if self.eat("[") {
+            let first = self.parse_item()?;
+            self.expect(",", ParseErrorKind::ExpectedCloseBracket)?;
+            self.skip_whitespace();
+            let last = self.parse_item()?;
+            self.expect("]", ParseErrorKind::ExpectedCloseBracket)?;
+            return self.checked_range(first, last);
+        }
+
+        let first = self.parse_endpoint()?;
+        if self.eat("..=") || self.eat(":") || self.eat(self.sep) {
+            let last = self.parse_endpoint()?;
+            self.checked_range(first, last)
+        } else if self.eat("..") {
+            let end = self.parse_endpoint()?;
+            match end.predecessor() {
+                Some(last) if first <= last => Ok(first..=last),
+                _ => Err(ParseError {
+                    kind: ParseErrorKind::EmptyRange,
+                    pos: self.range_pos,
+                }),
+            }
+        } else {
+            Ok(first.clone()..=first)
+        }
+    }
+
+    fn checked_range(&self, first: T, last: T) -> Result<RangeInclusive<T>, ParseError> {
+        if first <= last {
+            Ok(first..=last)
+        } else {
+            Err(ParseError {
+                kind: ParseErrorKind::ReversedRange,
+                pos: self.range_pos,
+            })
+        }
+    }
+
+    fn parse_next(&mut self) -> Result<Option<RangeInclusive<T>>, ParseError> {
+        self.skip_whitespace();
+        if self.rest().is_empty() {
+            return if self.need_range {
+                Err(self.error(ParseErrorKind::ExpectedItem))
+            } else {
+                Ok(None)
+            };
+        }
+
+        self.range_pos = self.pos;
+        let range = self.parse_range()?;
+
+        self.skip_whitespace();
+        self.need_range = !self.rest().is_empty();
+        if self.need_range {
+            self.expect(",", ParseErrorKind::ExpectedComma)?;
+        }
+
+        Ok(Some(range))
+    }
+}
+
+impl<'a, T> Iterator for ParseRanges<'a, T>
+where
+    T: ParseItem + IsAdjacent + PartialOrd + Clone,
+{
+    type Item = Result<RangeInclusive<T>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let result = self.parse_next();
+        if !matches!(result, Ok(Some(_))) {
+            self.done = true;
+        }
+        result.transpose()
+    }
+}
+
+impl<'a, T> core::iter::FusedIterator for ParseRanges<'a, T> where
+    T: ParseItem + IsAdjacent + PartialOrd + Clone
+{
+}
+
+#[test]
+fn test_parse_round_trip() {
+    use crate::{debug_adjacent, NegativeStyle, Pretty, RangeNotation};
+    use core::fmt::Debug;
+
+    fn round_trip<T>(items: &[T])
+    where
+        T: Debug + ParseItem + IsAdjacent + PartialOrd + Clone,
+    {
+        for notation in [
+            RangeNotation::Dash,
+            RangeNotation::Inclusive,
+            RangeNotation::HalfOpen,
+            RangeNotation::Interval,
+            RangeNotation::Colon,
+        ] {
+            for negatives in [
+                NegativeStyle::Auto,
+                NegativeStyle::Parenthesize,
+                NegativeStyle::Keep,
+            ] {
+                let dump = debug_adjacent(items)
+                    .notation(notation)
+                    .negatives(negatives)
+                    .pretty(Pretty::Off);
+                for text in [format!("{:?}", dump), format!("{:#x?}", dump)] {
+                    let mut parsed: Vec<T> = Vec::new();
+                    for range in parse_ranges::<T>(&text) {
+                        let (mut item, last) = range
+                            .unwrap_or_else(|e| panic!("{:?}: {}", text, e))
+                            .into_inner();
+                        while item != last {
+                            let next = item.successor().unwrap();
+                            parsed.push(item);
+                            item = next;
+                        }
+                        parsed.push(item);
+                    }
+                    assert_eq!(parsed, items, "text: {:?}", text);
+                }
+            }
+        }
+    }
+
+    macro_rules! ints {
+        ($($t:ty),*) => {
+            $(
+                round_trip::<$t>(&[]);
+                round_trip::<$t>(&[
+                    <$t>::MIN,
+                    <$t>::MIN + 1,
+                    <$t>::MIN + 1,
+                    0,
+                    1,
+                    2,
+                    5,
+                    <$t>::MAX - 1,
+                    <$t>::MAX,
+                    7,
+                ]);
+            )*
+        };
+    }
+    ints!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+    round_trip(&[
+        '\0',
+        '\u{1}',
+        'a',
+        'b',
+        'c',
+        '\'',
+        '"',
+        '\\',
+        '\n',
+        '\t',
+        '\r',
+        'é',
+        '\u{301}',
+        '\u{d7ff}',
+        '\u{e000}',
+        '\u{10fffe}',
+        '\u{10ffff}',
+    ]);
+}
+
+#[test]
+fn test_parse_errors() {
+    fn error(text: &str) -> ParseError {
+        parse_ranges::<i32>(text)
+            .find_map(Result::err)
+            .expect("expected an error")
+    }
+
+    let kind = |kind, pos| ParseError { kind, pos };
+    assert_eq!(error("1, "), kind(ParseErrorKind::ExpectedItem, 3));
+    assert_eq!(error("1 2"), kind(ParseErrorKind::ExpectedComma, 2));
+    assert_eq!(error("1, 5-"), kind(ParseErrorKind::ExpectedItem, 5));
+    assert_eq!(error("(-1-2"), kind(ParseErrorKind::ExpectedCloseParen, 3));
+    assert_eq!(
+        error("[1, 2"),
+        kind(ParseErrorKind::ExpectedCloseBracket, 5)
+    );
+    assert_eq!(error("1, 5-3"), kind(ParseErrorKind::ReversedRange, 3));
+    assert_eq!(error("0, 4..4"), kind(ParseErrorKind::EmptyRange, 3));
+    assert_eq!(error("99999999999"), kind(ParseErrorKind::ExpectedItem, 0));
+
+    let mut ranges = parse_ranges::<i32>("1, x, 3");
+    assert_eq!(ranges.next(), Some(Ok(1..=1)));
+    assert!(matches!(ranges.next(), Some(Err(_))));
+    assert_eq!(ranges.next(), None);
+
+    assert_eq!(
+        parse_ranges::<u32>(" 0x10..=0x1f , 7 to 9 ")
+            .sep(" to ")
+            .collect::<Result<Vec<_>, _>>(),
+        Ok(vec![16..=31, 7..=9])
+    );
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_parse_items() {
+    assert_eq!(parse_items::<u8>(""), Ok(vec![]));
+    assert_eq!(
+        parse_items::<char>("'a'-'c', 'x'"),
+        Ok(vec!['a', 'b', 'c', 'x'])
+    );
+    assert_eq!(
+        parse_items::<u8>("250..=255, 3").unwrap(),
+        [250, 251, 252, 253, 254, 255, 3]
+    );
+    assert_eq!(parse_items::<i32>("0xfffffffb, 0x7"), Ok(vec![-5, 7]));
+    assert_eq!(parse_items::<i8>("0xfe-0x1"), Ok(vec![-2, -1, 0, 1]));
+    assert_eq!(parse_items::<usize>("0x3-0x5"), Ok(vec![3, 4, 5]));
+    assert_eq!(parse_items::<isize>("-2..=-1"), Ok(vec![-2, -1]));
+}
@@ -0,0 +1,727 @@
+//! Finds runs of adjacent items in a slice.
+//!
+//! This is the engine behind [`DebugAdjacent`](crate::DebugAdjacent) and
+//! [`DebugAdjacentBy`](crate::DebugAdjacentBy). It is public so that code which needs the runs
+//! themselves (allocators, extent maps, and so on) can use the same logic that the `Debug` output
+//! uses, rather than formatting a string and parsing it back.
+
+use crate::write::Steps;
+use crate::{Direction, IsAdjacent, Options, Repeats, Stride};
+use core::borrow::Borrow;
+use core::fmt::{Debug, Formatter};
+use core::iter::FusedIterator;
+use core::ops::Range;
+
+/// Returns an iterator over the runs of adjacent items in `items`.
+///
+/// The `IsAdjacent` trait defines whether two values in `T` are adjacent.
+///
+/// # Example
+/// ```
+/// use dbg_ranges::runs;
+///
+/// let items = [10u32, 12, 13, 14, 15, 20];
+/// let lens: Vec<usize> = runs(&items).map(|run| run.len).collect();
+/// assert_eq!(lens, [1, 4, 1]);
+/// ```
+pub fn runs<T: IsAdjacent>(items: &[T]) -> Runs<'_, T, fn(&T, &T) -> bool> {
+    Runs::new(items, T::is_adjacent)
+}
+
+/// Returns an iterator over the runs of adjacent items in `items`.
+///
+/// The `is_adjacent` parameter defines whether two values in `T` are adjacent.
+pub fn runs_by<T, F: Fn(&T, &T) -> bool>(items: &[T], is_adjacent: F) -> Runs<'_, T, F> {
+    Runs::new(items, is_adjacent)
+}
+
+/// A run of adjacent items, found by [`Runs`].
+///
+/// A run always contains at least one item. If it contains exactly one item, then `first` and
+/// `last` refer to the same item.
+pub struct Run<'a, T> {
+    /// The first item in the run
+    pub first: &'a T,
+
+    /// The last item in the run
+    pub last: &'a T,
+
+    /// The number of items in the run
+    pub len: usize,
+
+    /// The index of `first` within the slice that was searched
+    pub start_index: usize,
+}
+
+impl<'a, T> Run<'a, T> {
+    /// Returns `true` if the run contains exactly one item.
+    pub fn is_single(&self) -> bool {
+        self.len == 1
+    }
+
+    /// Returns the range of indices that the run covers within the slice that was searched.
+    pub fn indices(&self) -> Range<usize> {
+        self.start_index..self.start_index + self.len
+    }
+}
+
+impl<'a, T> Copy for Run<'a, T> {}
+
+impl<'a, T> Clone for Run<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T: Debug> Debug for Run<'a, T> {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        f.debug_struct("Run")
+            .field("first", self.first)
+            .field("last", self.last)
+            .field("len", &self.len)
+            .field("start_index", &self.start_index)
+            .finish()
+    }
+}
+
+impl<'a, T: PartialEq> PartialEq for Run<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.first == other.first
+            && self.last == other.last
+            && self.len == other.len
+            && self.start_index == other.start_index
+    }
+}
+
+impl<'a, T: Eq> Eq for Run<'a, T> {}
+
+/// Iterates the runs of adjacent items in a slice.
+///
+/// Each item in the slice belongs to exactly one run, and the runs are returned in the order in
+/// which they appear in the slice. Iterating from the back returns the same runs in reverse order.
+///
+/// Use [`runs`] or [`runs_by`] to create this type.
+pub struct Runs<'a, T, F> {
+    /// The items that have not yet been returned
+    iter: core::slice::Iter<'a, T>,
+
+    /// The index of the next item that `iter` will return from the front
+    start_index: usize,
+
+    /// The function that tests for adjacency
+    is_adjacent: F,
+
+    /// Runs with fewer items than this are returned as single items
+    min_run: usize,
+}
+
+impl<'a, T, F> Runs<'a, T, F>
+where
+    F: Fn(&T, &T) -> bool,
+{
+    /// Constructor
+    pub fn new(items: &'a [T], is_adjacent: F) -> Self {
+        Self {
+            iter: items.iter(),
+            start_index: 0,
+            is_adjacent,
+            min_run: 2,
+        }
+    }
+
+    /// Sets the minimum number of items in a run. Runs of adjacent items that are shorter than
+    /// this are returned as runs of one item each. The default is 2, which returns every run of
+    /// adjacent items as it is found.
+    pub fn min_run(mut self, min_run: usize) -> Self {
+        self.min_run = min_run;
+        self
+    }
+}
+
+impl<'a, T, F: Clone> Clone for Runs<'a, T, F> {
+    fn clone(&self) -> Self {
+        Self {
+            iter: self.iter.clone(),
+            start_index: self.start_index,
+            is_adjacent: self.is_adjacent.clone(),
+            min_run: self.min_run,
+        }
+    }
+}
+
+impl<'a, T, F> Iterator for Runs<'a, T, F>
+where
+    F: Fn(&T, &T) -> bool,
+{
+    type Item = Run<'a, T>;
+
+    fn next(&mut self) -> Option<Run<'a, T>> {
+        let step = |a: &&T, b: &&T| (self.is_adjacent)(a, b).then_some(1);
+        let span = next_span(&mut self.iter, step, self.min_run, Direction::Ascending)?;
+        let run = Run {
+            first: span.first,
+            last: *span.last(),
+            len: span.len as usize,
+            start_index: self.start_index,
+        };
+        self.start_index += run.len;
+        Some(run)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.iter.len();
+        (n.min(1), Some(n))
+    }
+}
+
+impl<'a, T, F> DoubleEndedIterator for Runs<'a, T, F>
+where
+    F: Fn(&T, &T) -> bool,
+{
+    fn next_back(&mut self) -> Option<Run<'a, T>> {
+        let is_adjacent = |a: &&T, b: &&T| (self.is_adjacent)(a, b);
+        let span = next_span_back(&mut self.iter, is_adjacent, self.min_run)?;
+        Some(Run {
+            first: span.first,
+            last: *span.last(),
+            len: span.len as usize,
+            start_index: self.start_index + self.iter.len(),
+        })
+    }
+}
+
+impl<'a, T, F> FusedIterator for Runs<'a, T, F> where F: Fn(&T, &T) -> bool {}
+
+/// A run found in the items produced by an iterator.
+///
+/// Unlike [`Run`], this owns the items that it holds, so that it can describe runs found in
+/// iterators that produce values rather than references. `R` is an iterator that produces the
+/// items of the run, if the caller needs them.
+pub(crate) struct Span<X, R = ()> {
+    /// The first item in the run
+    pub(crate) first: X,
+
+    /// The last item in the run, or `None` if the run contains only `first`
+    pub(crate) last: Option<X>,
+
+    /// The number of items in the run. This is a `u128`, rather than a `usize`, because runs of
+    /// missing values can be longer than any slice. It saturates at `u128::MAX` for a run of
+    /// every value of a 128-bit type, which [`run_len`] treats as unknown.
+    ///
+    /// A span with no items is not a run, but an entry that is written between runs, such as a
+    /// hole between two extents. See [`Span::is_run`].
+    pub(crate) len: u128,
+
+    /// `true` if each item is adjacent to the item before it, rather than to the item after it
+    pub(crate) descending: bool,
+
+    /// The distance between consecutive items, if the run was found by [`Stride`] with a step
+    /// other than 1, or 0 if the run was found by [`Repeats`]
+    pub(crate) step: Option<u128>,
+
+    /// The number of items that are missing from the run, if gaps are allowed by
+    /// [`Options::max_gap`]
+    pub(crate) missing: u128,
+
+    /// Produces the items of the run, starting with `first`
+    pub(crate) items: R,
+}
+
+/// Returns the number of items in a run, or `None` if it is too large to be known. See
+/// [`Span::len`].
+pub(crate) fn run_len(len: u128) -> Option<u128> {
+    (len != u128::MAX).then_some(len)
+}
+
+/// Adds up the number of items in runs, or returns `None` if the total is too large to be known.
+pub(crate) fn total_len(lens: impl Iterator<Item = u128>) -> Option<u128> {
+    lens.map(run_len)
+        .try_fold(0u128, |total, len| run_len(total.checked_add(len?)?))
+}
+
+impl<X, R> Span<X, R> {
+    /// Returns the last item in the run, which is `first` if the run has only one item.
+    pub(crate) fn last(&self) -> &X {
+        self.last.as_ref().unwrap_or(&self.first)
+    }
+
+    /// Returns `false` if this span has no items, and so is written between runs rather than
+    /// being one. Such spans are left out of the header, the elision and the statistics.
+    pub(crate) fn is_run(&self) -> bool {
+        self.len != 0
+    }
+}
+
+/// Removes the first run from the front of `iter` and returns it.
+///
+/// `iter` is cloned in order to look at the item after the end of the run, so that the item
+/// is left in `iter` for the next run. If the run has fewer than `min_run` items, then only its
+/// first item is removed, and the rest are left for the following calls. `direction` selects
+/// whether increasing runs, decreasing runs or both are found.
+///
+/// `step` returns the distance from one item to the next, or `None` if the second item cannot
+/// follow the first in a run. Every step in a run must be the same, and strided runs, whose step
+/// is neither 0 nor 1, must have at least 3 items.
+pub(crate) fn next_span<I, F>(
+    iter: &mut I,
+    step: F,
+    min_run: usize,
+    direction: Direction,
+) -> Option<Span<I::Item>>
+where
+    I: Iterator + Clone,
+    F: Fn(&I::Item, &I::Item) -> Option<u128>,
+{
+    let first = iter.next()?;
+    let start = iter.clone();
+    let mut last: Option<I::Item> = None;
+    let mut len: u128 = 1;
+    let mut descending = direction == Direction::Descending;
+    let mut run_step: Option<u128> = None;
+
+    loop {
+        let mut ahead = iter.clone();
+        let Some(next) = ahead.next() else {
+            break;
+        };
+        let prev = last.as_ref().unwrap_or(&first);
+        let found = if descending {
+            step(&next, prev)
+        } else {
+            match step(prev, &next) {
+                None if len == 1 && direction == Direction::Both => {
+                    let found = step(&next, prev);
+                    descending = found.is_some();
+                    found
+                }
+                found => found,
+            }
+        };
+        match found {
+            Some(found) if run_step.is_none() || run_step == Some(found) => {
+                run_step = Some(found);
+            }
+            _ => break,
+        }
+        *iter = ahead;
+        last = Some(next);
+        len += 1;
+    }
+
+    let step = run_step.filter(|&step| step != 1);
+    if len < min_run as u128 || (step.is_some_and(|step| step != 0) && len < 3) {
+        *iter = start;
+        return Some(Span {
+            first,
+            last: None,
+            len: 1,
+            descending: false,
+            step: None,
+            missing: 0,
+            items: (),
+        });
+    }
+
+    Some(Span {
+        first,
+        last,
+        len,
+        descending: descending && len > 1,
+        step,
+        missing: 0,
+        items: (),
+    })
+}
+
+/// Removes the last run from the back of `iter` and returns it. This is the mirror image of
+/// [`next_span`].
+pub(crate) fn next_span_back<I, F>(
+    iter: &mut I,
+    is_adjacent: F,
+    min_run: usize,
+) -> Option<Span<I::Item>>
+where
+    I: DoubleEndedIterator + Clone,
+    F: Fn(&I::Item, &I::Item) -> bool,
+{
+    let end = iter.next_back()?;
+    let start = iter.clone();
+    let mut first: Option<I::Item> = None;
+    let mut len: u128 = 1;
+
+    loop {
+        let mut behind = iter.clone();
+        match behind.next_back() {
+            Some(prev) if is_adjacent(&prev, first.as_ref().unwrap_or(&end)) => {
+                *iter = behind;
+                first = Some(prev);
+                len += 1;
+            }
+            _ => break,
+        }
+    }
+
+    if len < min_run as u128 {
+        *iter = start;
+        return Some(Span {
+            first: end,
+            last: None,
+            len: 1,
+            descending: false,
+            step: None,
+            missing: 0,
+            items: (),
+        });
+    }
+
+    Some(match first {
+        Some(first) => Span {
+            first,
+            last: Some(end),
+            len,
+            descending: false,
+            step: None,
+            missing: 0,
+            items: (),
+        },
+        None => Span {
+            first: end,
+            last: None,
+            len,
+            descending: false,
+            step: None,
+            missing: 0,
+            items: (),
+        },
+    })
+}
+
+/// Iterates the runs of adjacent items produced by an iterator.
+///
+/// The iterator is cloned whenever the run detection needs to look ahead, so this is best used
+/// with iterators that are cheap to clone.
+///
+/// `distance` is used to find strided runs and runs with gaps, as described by
+/// [`IsAdjacent::distance`], and `is_repeat` is used to find runs of repeated items. Types that
+/// cannot compute these pass functions that always return `None` or `false`.
+#[derive(Clone)]
+pub(crate) struct IterRuns<I, F, D, E> {
+    iter: I,
+    is_adjacent: F,
+    distance: D,
+    is_repeat: E,
+    min_run: usize,
+    direction: Direction,
+    stride: Stride,
+    repeats: Repeats,
+    max_gap: usize,
+}
+
+impl<I, F, D, E> IterRuns<I, F, D, E>
+where
+    I: Iterator + Clone,
+    F: Fn(&I::Item, &I::Item) -> bool,
+    D: Fn(&I::Item, &I::Item) -> Option<u128>,
+    E: Fn(&I::Item, &I::Item) -> bool,
+{
+    pub(crate) fn new(
+        iter: I,
+        is_adjacent: F,
+        distance: D,
+        is_repeat: E,
+        options: &Options,
+    ) -> Self {
+        Self {
+            iter,
+            is_adjacent,
+            distance,
+            is_repeat,
+            min_run: options.min_run,
+            direction: options.direction,
+            stride: options.stride,
+            repeats: options.repeats,
+            max_gap: options.max_gap,
+        }
+    }
+}
+
+impl<I, F, D, E> Iterator for IterRuns<I, F, D, E>
+where
+    I: Iterator + Clone,
+    F: Fn(&I::Item, &I::Item) -> bool,
+    D: Fn(&I::Item, &I::Item) -> Option<u128>,
+    E: Fn(&I::Item, &I::Item) -> bool,
+{
+    type Item = Span<I::Item, I>;
+
+    fn next(&mut self) -> Option<Span<I::Item, I>> {
+        let step = |a: &I::Item, b: &I::Item| {
+            match self.repeats {
+                Repeats::Off => {}
+                _ if (self.is_repeat)(a, b) => return Some(0),
+                Repeats::Only => return None,
+                Repeats::WithRanges => {}
+            }
+            let strided = match self.stride {
+                Stride::Off => None,
+                // Equal items are only collapsed by `Repeats`, so a fixed step of 0 finds no runs.
+                Stride::Fixed(stride) => (self.distance)(a, b).filter(|&d| d == stride && d != 0),
+                Stride::Infer => (self.distance)(a, b).filter(|&d| d != 0),
+            };
+            let gap = |d: u128| d > 1 && d - 1 <= self.max_gap as u128;
+            strided
+                .or_else(|| (self.is_adjacent)(a, b).then_some(1))
+                .or_else(|| (self.distance)(a, b).filter(|&d| gap(d)).map(|_| 1))
+        };
+        let items = self.iter.clone();
+        let span = next_span(&mut self.iter, step, self.min_run, self.direction)?;
+
+        let missing = match &span.last {
+            Some(last) if self.max_gap != 0 && span.step.is_none() => {
+                let distance = if span.descending {
+                    (self.distance)(last, &span.first)
+                } else {
+                    (self.distance)(&span.first, last)
+                };
+                distance.map_or(0, |d| d - (span.len - 1))
+            }
+            _ => 0,
+        };
+
+        Some(Span {
+            first: span.first,
+            last: span.last,
+            len: span.len,
+            descending: span.descending,
+            step: span.step,
+            missing,
+            items,
+        })
+    }
+}
+
+/// Iterates the runs of values that are missing from a sorted slice, within a range of values.
+///
+/// Each gap between two items is a single run, so the runs are found from the items on either
+/// side of the gap, without visiting the missing values themselves. Items that are out of order
+/// or repeated are skipped. The values at the ends of each gap are computed with `steps`, so the
+/// items do not need to be cloned.
+pub(crate) struct GapRuns<'a, T> {
+    /// The items that have not yet been visited
+    items: core::slice::Iter<'a, T>,
+
+    /// The lowest value that has not yet been accounted for, or `None` if there is none
+    cursor: Option<Cursor<'a, T>>,
+
+    /// The highest value that can be missing, or `None` if the range is empty
+    end: Option<&'a T>,
+
+    /// Moves between values
+    steps: Steps<T>,
+
+    /// Runs with fewer items than this are returned as single items
+    min_run: usize,
+
+    /// The start, the offset of the next value, and the length of a run that is being returned
+    /// as single items, because it is shorter than `min_run`
+    singles: Option<(Cursor<'a, T>, usize, usize)>,
+}
+
+/// A position in a [`GapRuns`], relative to a value that the caller owns.
+enum Cursor<'a, T> {
+    /// The position of the value itself
+    At(&'a T),
+
+    /// The position of the value after it
+    After(&'a T),
+}
+
+impl<'a, T> Copy for Cursor<'a, T> {}
+
+impl<'a, T> Clone for Cursor<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+/// A value in a run found by [`GapRuns`], which is either one of the caller's values or a value
+/// computed from one.
+pub(crate) enum GapValue<'a, T> {
+    Borrowed(&'a T),
+    Owned(T),
+}
+
+impl<'a, T> Borrow<T> for GapValue<'a, T> {
+    fn borrow(&self) -> &T {
+        match self {
+            Self::Borrowed(value) => value,
+            Self::Owned(value) => value,
+        }
+    }
+}
+
+impl<'a, T> GapRuns<'a, T> {
+    /// `bounds` is the first and last value that can be missing, or `None` if there are none.
+    pub(crate) fn new(
+        items: &'a [T],
+        bounds: Option<(&'a T, &'a T)>,
+        steps: Steps<T>,
+        min_run: usize,
+    ) -> Self {
+        let (start, end) = bounds.unzip();
+        Self {
+            items: items.iter(),
+            cursor: start.map(Cursor::At),
+            end,
+            steps,
+            min_run,
+            singles: None,
+        }
+    }
+
+    /// Returns the distance from `cursor` to `to`, or `None` if `to` is before `cursor`.
+    fn distance(&self, cursor: Cursor<'a, T>, to: &T) -> Option<u128> {
+        match cursor {
+            Cursor::At(value) => (self.steps.distance)(value, to),
+            Cursor::After(value) => (self.steps.distance)(value, to)?.checked_sub(1),
+        }
+    }
+
+    /// Returns the value `offset` steps after `cursor`.
+    fn value(&self, cursor: Cursor<'a, T>, offset: usize) -> Option<GapValue<'a, T>> {
+        let (mut value, offset) = match cursor {
+            Cursor::At(value) if offset == 0 => return Some(GapValue::Borrowed(value)),
+            Cursor::At(value) => ((self.steps.successor)(value)?, offset - 1),
+            Cursor::After(value) => ((self.steps.successor)(value)?, offset),
+        };
+        for _ in 0..offset {
+            value = (self.steps.successor)(&value)?;
+        }
+        Some(GapValue::Owned(value))
+    }
+
+    /// Returns the start, last value and length of the next gap. The length saturates, as
+    /// described by [`Span::len`].
+    fn next_gap(&mut self) -> Option<(Cursor<'a, T>, GapValue<'a, T>, u128)> {
+        loop {
+            let end = self.end?;
+            let cursor = self.cursor.take()?;
+            let to_end = self.distance(cursor, end)?;
+
+            let Some(item) = self.items.next() else {
+                let len = to_end.saturating_add(1);
+                return Some((cursor, GapValue::Borrowed(end), len));
+            };
+
+            match self.distance(cursor, item) {
+                // The item is before the cursor, so it is out of order or repeated.
+                None => self.cursor = Some(cursor),
+                Some(0) => self.cursor = Some(Cursor::After(item)),
+                Some(d) if d > to_end => {
+                    return Some((cursor, GapValue::Borrowed(end), to_end + 1));
+                }
+                Some(d) => {
+                    self.cursor = Some(Cursor::After(item));
+                    let last = (self.steps.predecessor)(item)?;
+                    return Some((cursor, GapValue::Owned(last), d));
+                }
+            }
+        }
+    }
+}
+
+impl<'a, T> Clone for GapRuns<'a, T> {
+    fn clone(&self) -> Self {
+        Self {
+            items: self.items.clone(),
+            cursor: self.cursor,
+            end: self.end,
+            steps: self.steps,
+            min_run: self.min_run,
+            singles: self.singles,
+        }
+    }
+}
+
+impl<'a, T> Iterator for GapRuns<'a, T> {
+    type Item = Span<GapValue<'a, T>, core::iter::Empty<GapValue<'a, T>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (first, last, len) = match self.singles.take() {
+            Some((start, offset, len)) => {
+                if offset + 1 < len {
+                    self.singles = Some((start, offset + 1, len));
+                }
+                (self.value(start, offset)?, None, 1)
+            }
+            None => {
+                let (start, last, len) = self.next_gap()?;
+                if len == 1 {
+                    (self.value(start, 0)?, None, 1)
+                } else if len < self.min_run as u128 {
+                    // The gap is shorter than `min_run`, so its length fits in a `usize`.
+                    self.singles = Some((start, 1, len as usize));
+                    (self.value(start, 0)?, None, 1)
+                } else {
+                    (self.value(start, 0)?, Some(last), len)
+                }
+            }
+        };
+
+        Some(Span {
+            first,
+            last,
+            len,
+            descending: false,
+            step: None,
+            missing: 0,
+            items: core::iter::empty(),
+        })
+    }
+}
+
+#[test]
+fn test_runs() {
+    let items = [10u32, 12, 13, 14, 15, 20, 21];
+    let found: Vec<(u32, u32, usize, usize)> = runs(&items)
+        .map(|run| (*run.first, *run.last, run.len, run.start_index))
+        .collect();
+    assert_eq!(found, [(10, 10, 1, 0), (12, 15, 4, 1), (20, 21, 2, 5)]);
+
+    let mut backward: Vec<Run<u32>> = runs(&items).rev().collect();
+    backward.reverse();
+    assert_eq!(backward, runs(&items).collect::<Vec<_>>());
+
+    let mut both = runs(&items);
+    assert_eq!(both.next().map(|run| run.indices()), Some(0..1));
+    assert_eq!(both.next_back().map(|run| run.indices()), Some(5..7));
+    assert_eq!(both.next().map(|run| run.indices()), Some(1..5));
+    assert!(both.next_back().is_none());
+    assert!(both.next().is_none());
+
+    assert_eq!(runs::<u32>(&[]).count(), 0);
+}
+
+#[test]
+fn test_runs_min_run() {
+    let items = [1u32, 2, 5, 6, 7, 9, 11, 12];
+    let forward: Vec<(u32, usize)> = runs(&items)
+        .min_run(3)
+        .map(|run| (*run.first, run.len))
+        .collect();
+    assert_eq!(forward, [(1, 1), (2, 1), (5, 3), (9, 1), (11, 1), (12, 1)]);
+
+    let mut backward: Vec<Run<u32>> = runs(&items).min_run(3).rev().collect();
+    backward.reverse();
+    assert_eq!(backward, runs(&items).min_run(3).collect::<Vec<_>>());
+    assert_eq!(backward[3].start_index, 5);
+}
+
+#[test]
+fn test_runs_by() {
+    let items = [1u32, 3, 5, 6, 8];
+    let lens: Vec<usize> = runs_by(&items, |&a, &b| a + 2 == b)
+        .map(|run| run.len)
+        .collect();
+    assert_eq!(lens, [3, 2]);
+}
@@ -0,0 +1,156 @@
+//! Summary statistics for the runs in a list.
+
+use crate::runs::{IterRuns, Span};
+use crate::{IsAdjacent, Options};
+use core::borrow::Borrow;
+use core::fmt::{Debug, Formatter};
+
+/// Returns statistics about the runs of adjacent items in `items`.
+///
+/// The `IsAdjacent` trait defines whether two values in `T` are adjacent, and the distance
+/// between runs.
+///
+/// # Example
+/// ```
+/// use dbg_ranges::run_stats;
+///
+/// let blocks = [42u32, 100, 101, 102, 103, 104, 20, 31, 32, 33, 34];
+/// let stats = run_stats(&blocks);
+/// assert_eq!((stats.items, stats.runs), (11, 4));
+/// assert_eq!((stats.longest, stats.shortest), (5, 1));
+/// assert_eq!(stats.largest_gap, Some(83));
+/// assert_eq!(stats.mean_len(), 2.75);
+/// assert_eq!(stats.fragmentation(), 4.0 / 11.0);
+/// ```
+pub fn run_stats<T: IsAdjacent>(items: &[T]) -> RunStats {
+    RunStats::new(items)
+}
+
+/// Returns statistics about the runs of adjacent items in `items`.
+///
+/// The `is_adjacent` parameter defines whether two values in `T` are adjacent. The distance
+/// between runs is unknown, so [`RunStats::largest_gap`] is `None`.
+pub fn run_stats_by<T, F: Fn(&T, &T) -> bool>(items: &[T], is_adjacent: F) -> RunStats {
+    RunStats::by(items, is_adjacent)
+}
+
+/// Statistics about the runs in a list, such as the fragmentation of the blocks of a file.
+///
+/// The runs are the ones that the wrapper types write with the default [`Options`]. The
+/// statistics can be added to the output of the wrapper types with [`Options::stats`].
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct RunStats {
+    /// The number of items
+    pub items: usize,
+
+    /// The number of runs
+    pub runs: usize,
+
+    /// The number of items in the longest run, or 0 if there are no runs
+    pub longest: usize,
+
+    /// The number of items in the shortest run, or 0 if there are no runs
+    pub shortest: usize,
+
+    /// The largest number of values between the last item of a run and the first item of the
+    /// next run, in either direction, or `None` if there are fewer than two runs or the distance
+    /// between items is not known.
+    pub largest_gap: Option<u128>,
+}
+
+impl RunStats {
+    /// Computes the statistics for the runs of adjacent items in `items`, using the `IsAdjacent`
+    /// trait.
+    pub fn new<T: IsAdjacent>(items: &[T]) -> Self {
+        let runs = IterRuns::new(
+            items.iter(),
+            |a: &&T, b: &&T| a.is_adjacent(b),
+            |a: &&T, b: &&T| T::distance(a, b),
+            |a: &&T, b: &&T| T::is_repeat(a, b),
+            &Options::default(),
+        );
+        Self::from_spans(runs, T::distance)
+    }
+
+    /// Computes the statistics for the runs of adjacent items in `items`, using a function to
+    /// test for adjacency.
+    pub fn by<T, F: Fn(&T, &T) -> bool>(items: &[T], is_adjacent: F) -> Self {
+        let runs = IterRuns::new(
+            items.iter(),
+            |a: &&T, b: &&T| is_adjacent(a, b),
+            |_: &&T, _: &&T| None,
+            |_: &&T, _: &&T| false,
+            &Options::default(),
+        );
+        Self::from_spans(runs, |_: &T, _: &T| None)
+    }
+
+    /// Computes the statistics for runs that have already been found. `distance` finds the gaps
+    /// between them.
+    pub(crate) fn from_spans<T, Q, R>(
+        runs: impl Iterator<Item = Span<Q, R>>,
+        distance: impl Fn(&T, &T) -> Option<u128>,
+    ) -> Self
+    where
+        Q: Borrow<T>,
+    {
+        let mut stats = Self::default();
+        let mut prev: Option<Span<Q, R>> = None;
+        for run in runs.filter(Span::is_run) {
+            let len = usize::try_from(run.len).unwrap_or(usize::MAX);
+            stats.items = stats.items.saturating_add(len);
+            stats.runs += 1;
+            stats.longest = stats.longest.max(len);
+            stats.shortest = match stats.runs {
+                1 => len,
+                _ => stats.shortest.min(len),
+            };
+
+            if let Some(prev) = &prev {
+                let (a, b) = (prev.last().borrow(), run.first.borrow());
+                let gap = distance(a, b).or_else(|| distance(b, a));
+                if let Some(gap) = gap {
+                    let gap = gap.saturating_sub(1);
+                    stats.largest_gap = Some(stats.largest_gap.map_or(gap, |g| g.max(gap)));
+                }
+            }
+            prev = Some(run);
+        }
+        stats
+    }
+
+    /// Returns the mean number of items in a run, or 0 if there are no runs.
+    pub fn mean_len(&self) -> f64 {
+        if self.runs == 0 {
+            0.0
+        } else {
+            self.items as f64 / self.runs as f64
+        }
+    }
+
+    /// Returns the number of runs divided by the number of items, or 0 if there are no items.
+    ///
+    /// This is 1 if no two items are adjacent, and approaches 0 as the items form fewer, longer
+    /// runs.
+    pub fn fragmentation(&self) -> f64 {
+        if self.items == 0 {
+            0.0
+        } else {
+            self.runs as f64 / self.items as f64
+        }
+    }
+}
+
+impl Debug for RunStats {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        f.debug_struct("RunStats")
+            .field("items", &self.items)
+            .field("runs", &self.runs)
+            .field("longest", &self.longest)
+            .field("shortest", &self.shortest)
+            .field("mean_len", &self.mean_len())
+            .field("largest_gap", &self.largest_gap)
+            .field("fragmentation", &self.fragmentation())
+            .finish()
+    }
+}
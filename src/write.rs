@@ -0,0 +1,885 @@
+//! Writes runs to a `Formatter`.
+
+use crate::runs::{run_len, total_len, GapRuns, Span};
+use crate::{
+    GapStyle, IsAdjacent, NegativeStyle, Options, Pretty, RangeNotation, RunFormatter, RunRange,
+    RunStats, Stride,
+};
+use core::borrow::Borrow;
+use core::fmt::{Alignment, Debug, Formatter, Write};
+
+/// Writes each run as either a single item or a range, with runs separated by commas.
+///
+/// The parts of the list are written by `formatter`, and each item is written by `write_item`,
+/// which is usually the `fmt` method of `Debug` or `Display`. `steps` finds the item after the end
+/// of a range, for [`RangeNotation::HalfOpen`], and the items that are missing from a run with
+/// gaps. `runs` is cloned if the header or elision is enabled, in order to count the runs before
+/// writing them.
+///
+/// If the formatter has a width, then the width and fill apply to the whole list. The list is
+/// written once into a counter to measure it, and then written again after the padding. Both
+/// passes use a new formatter that has no width, but has the caller's other flags, such as
+/// `{:#?}`, `{:+?}` and `{:x?}`.
+pub(crate) fn fmt_runs<T, Q, R>(
+    f: &mut Formatter,
+    runs: impl Iterator<Item = Span<Q, R>> + Clone,
+    options: &Options,
+    formatter: &dyn RunFormatter,
+    steps: Steps<T>,
+    write_item: impl Fn(&T, &mut Formatter) -> core::fmt::Result,
+) -> core::fmt::Result
+where
+    Q: Borrow<T>,
+    R: Iterator<Item = Q> + Clone,
+{
+    let items = ItemWriter { steps, write_item };
+    fmt_padded(f, &|f: &mut Formatter| {
+        fmt_list(f, runs.clone(), options, formatter, &items)?;
+        if options.stats {
+            fmt_stats(f, runs.clone(), steps)?;
+        }
+        Ok(())
+    })
+}
+
+/// Writes the items of a slice in the shortest of three forms, for [`Options::shortest`].
+///
+/// The forms are the runs of items that are returned by `runs`, the same runs with
+/// [`Stride::Infer`], and the values that are missing between the first and last item, after a
+/// prefix such as `all of 0-4095 except `. The last form is only considered if the items are
+/// strictly increasing, according to `steps`. Each form is written into a counter to measure it,
+/// and ties are won by the earlier form. The statistics, if enabled, are those of the runs.
+pub(crate) fn fmt_shortest<T, Q, R, I>(
+    f: &mut Formatter,
+    items: &[T],
+    runs: impl Fn(&Options) -> I,
+    options: &Options,
+    formatter: &dyn RunFormatter,
+    steps: Steps<T>,
+    write_item: impl Fn(&T, &mut Formatter) -> core::fmt::Result,
+) -> core::fmt::Result
+where
+    I: Iterator<Item = Span<Q, R>> + Clone,
+    Q: Borrow<T>,
+    R: Iterator<Item = Q> + Clone,
+{
+    let writer = ItemWriter { steps, write_item };
+    let strided_options = Options {
+        stride: Stride::Infer,
+        ..*options
+    };
+
+    let present = |f: &mut Formatter| fmt_list(f, runs(options), options, formatter, &writer);
+    let strided = |f: &mut Formatter| {
+        fmt_list(
+            f,
+            runs(&strided_options),
+            &strided_options,
+            formatter,
+            &writer,
+        )
+    };
+    let complement = |f: &mut Formatter| fmt_complement(f, items, options, formatter, &writer);
+
+    let increasing = items.len() > 1
+        && items
+            .windows(2)
+            .all(|pair| matches!((steps.distance)(&pair[0], &pair[1]), Some(d) if d > 0));
+    let forms: [Option<Form>; 3] = [
+        Some(&present),
+        (options.stride == Stride::Off).then_some(&strided),
+        increasing.then_some(&complement),
+    ];
+
+    let flags = Flags::of(f);
+    let best = forms
+        .into_iter()
+        .flatten()
+        .min_by_key(|form| measure(flags, &FmtFn(form)))
+        .unwrap_or(&present);
+    fmt_padded(f, &|f: &mut Formatter| {
+        best(f)?;
+        if options.stats {
+            fmt_stats(f, runs(options), steps)?;
+        }
+        Ok(())
+    })
+}
+
+/// Writes the statistics of `runs` after a list, for [`Options::stats`].
+fn fmt_stats<T, Q, R>(
+    f: &mut Formatter,
+    runs: impl Iterator<Item = Span<Q, R>>,
+    steps: Steps<T>,
+) -> core::fmt::Result
+where
+    Q: Borrow<T>,
+{
+    f.write_str("; ")?;
+    RunStats::from_spans(runs, steps.distance).fmt(f)
+}
+
+/// A way of writing a list, which is one of the forms compared by [`fmt_shortest`].
+type Form<'f> = &'f dyn Fn(&mut Formatter) -> core::fmt::Result;
+
+/// Writes the values that are missing between the first and last item of a sorted slice, after
+/// a prefix that gives the range, e.g. `all of 0-4095 except 17, 900-901`. The header is left
+/// out, since the number of items that it would give is not the number of items in the slice.
+fn fmt_complement<T, W>(
+    f: &mut Formatter,
+    items: &[T],
+    options: &Options,
+    formatter: &dyn RunFormatter,
+    writer: &ItemWriter<T, W>,
+) -> core::fmt::Result
+where
+    W: Fn(&T, &mut Formatter) -> core::fmt::Result,
+{
+    let (Some(first), Some(last)) = (items.first(), items.last()) else {
+        return Ok(());
+    };
+
+    let len = (writer.steps.distance)(first, last).map_or(u128::MAX, |d| d.saturating_add(1));
+    let end = (writer.steps.successor)(last);
+    let end = end.as_ref().map(|end| writer.item(end));
+    let range = RunRange {
+        first: &writer.item(first),
+        last: &writer.item(last),
+        end: end.as_ref().map(|end| end as &dyn Debug),
+        len,
+        descending: false,
+        step: None,
+    };
+
+    f.write_str("all of ")?;
+    formatter.write_range(f, &range, options)?;
+    f.write_str(" except ")?;
+
+    let gaps = GapRuns::new(items, Some((first, last)), writer.steps, options.min_run);
+    let options = Options {
+        header: false,
+        ..*options
+    };
+    fmt_list(f, gaps, &options, formatter, writer)
+}
+
+/// Writes `list`, padded to the width of the formatter, if it has one.
+///
+/// The width and fill apply to the whole list. The list is written once into a counter to
+/// measure it, and then written again after the padding. Both passes pass on the caller's flags,
+/// so that the padding does not change how the items are written.
+fn fmt_padded(
+    f: &mut Formatter,
+    list: &dyn Fn(&mut Formatter) -> core::fmt::Result,
+) -> core::fmt::Result {
+    let Some(width) = f.width() else {
+        return list(f);
+    };
+
+    let flags = Flags::of(f);
+    let list = FmtFn(list);
+    let padding = width.saturating_sub(measure(flags, &list));
+    let (before, after) = match f.align() {
+        Some(Alignment::Right) => (padding, 0),
+        Some(Alignment::Center) => (padding / 2, padding - padding / 2),
+        _ => (0, padding),
+    };
+
+    let fill = f.fill();
+    for _ in 0..before {
+        f.write_char(fill)?;
+    }
+    flags.write(f, &list)?;
+    for _ in 0..after {
+        f.write_char(fill)?;
+    }
+    Ok(())
+}
+
+/// Moves between items, for types that implement [`IsAdjacent`]. Other types use
+/// [`Steps::none`], which cannot move between items.
+pub(crate) struct Steps<T> {
+    pub(crate) successor: fn(&T) -> Option<T>,
+    pub(crate) predecessor: fn(&T) -> Option<T>,
+    pub(crate) distance: fn(&T, &T) -> Option<u128>,
+}
+
+impl<T> Steps<T> {
+    pub(crate) fn none() -> Self {
+        Self {
+            successor: |_| None,
+            predecessor: |_| None,
+            distance: |_, _| None,
+        }
+    }
+}
+
+impl<T: IsAdjacent> Steps<T> {
+    pub(crate) fn from_trait() -> Self {
+        Self {
+            successor: T::successor,
+            predecessor: T::predecessor,
+            distance: T::distance,
+        }
+    }
+}
+
+impl<T> Copy for Steps<T> {}
+
+impl<T> Clone for Steps<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+/// The functions that [`fmt_runs`] uses to handle individual items.
+struct ItemWriter<T, W> {
+    steps: Steps<T>,
+    write_item: W,
+}
+
+impl<T, W> ItemWriter<T, W> {
+    /// Returns a value whose `Debug` implementation writes `item`.
+    fn item<'i>(&'i self, item: &'i T) -> FmtFn<impl Fn(&mut Formatter) -> core::fmt::Result + 'i>
+    where
+        W: Fn(&T, &mut Formatter) -> core::fmt::Result,
+    {
+        FmtFn(move |f: &mut Formatter| (self.write_item)(item, f))
+    }
+}
+
+/// Returns `true` if the output of `item` starts with `-`.
+///
+/// This writes `item` into a sink that stops at the first character, so it does not allocate.
+/// The formatting flags of the caller's `Formatter` are not applied, which only matters for flags
+/// that remove the sign, such as `{:x?}`. In that case the range is written in the unambiguous
+/// style anyway, which is harmless.
+pub(crate) fn is_negative(item: &dyn Debug) -> bool {
+    struct FirstChar(Option<char>);
+
+    impl Write for FirstChar {
+        fn write_str(&mut self, s: &str) -> core::fmt::Result {
+            match s.chars().next() {
+                Some(c) => {
+                    self.0 = Some(c);
+                    // Stop formatting; the rest of the output is not needed.
+                    Err(core::fmt::Error)
+                }
+                None => Ok(()),
+            }
+        }
+    }
+
+    let mut probe = FirstChar(None);
+    _ = write!(probe, "{:?}", item);
+    probe.0 == Some('-')
+}
+
+/// Writes the header, if enabled, and then the runs.
+fn fmt_list<T, Q, R, W>(
+    f: &mut Formatter,
+    runs: impl Iterator<Item = Span<Q, R>> + Clone,
+    options: &Options,
+    formatter: &dyn RunFormatter,
+    items: &ItemWriter<T, W>,
+) -> core::fmt::Result
+where
+    Q: Borrow<T>,
+    R: Iterator<Item = Q> + Clone,
+    W: Fn(&T, &mut Formatter) -> core::fmt::Result,
+{
+    let elide = options.head.is_some() || options.tail.is_some();
+
+    let (num_items, num_runs) = if options.header || elide {
+        let num_items = total_len(runs.clone().map(|run| run.len));
+        (num_items, runs.clone().filter(Span::is_run).count())
+    } else {
+        (None, 0)
+    };
+
+    let mut list = ListWriter::new(f, formatter, options.pretty);
+
+    if options.header {
+        // A count that is too large to be known is left out.
+        if let Some(num_items) = num_items {
+            write!(list.f, "{} item{} in ", num_items, plural(num_items))?;
+        }
+        write!(list.f, "{} run{}", num_runs, plural(num_runs as u128))?;
+        if num_runs != 0 || list.has_brackets() {
+            list.f.write_str(": ")?;
+        } else {
+            // Nothing follows the header, since the list has no runs and no brackets.
+            return Ok(());
+        }
+    }
+
+    // The indices of the runs that are left out and replaced with a marker.
+    let head = options.head.unwrap_or(0);
+    let tail = options.tail.unwrap_or(0);
+    let elided = if elide && num_runs > head.saturating_add(tail) {
+        head..num_runs - tail
+    } else {
+        0..0
+    };
+
+    let mut elided_items = Some(0u128);
+
+    // The index of the next run, which does not count the entries between runs.
+    let mut index = 0;
+
+    list.begin()?;
+    for run in runs {
+        let i = index;
+        if run.is_run() {
+            index += 1;
+        }
+        // An entry between runs is left out if either of the runs around it is.
+        if elided.contains(&i) || (!run.is_run() && i > 0 && elided.contains(&(i - 1))) {
+            elided_items = elided_items.and_then(|total| total_len([total, run.len].into_iter()));
+            if run.is_run() && i + 1 == elided.end {
+                list.entry(&|f: &mut Formatter| {
+                    let runs = elided.len();
+                    write!(f, "... {} more run{}", runs, plural(runs as u128))?;
+                    if let Some(items) = elided_items {
+                        write!(f, " ({} item{})", items, plural(items))?;
+                    }
+                    f.write_str(" ...")
+                })?;
+            }
+            continue;
+        }
+
+        list.entry(&|f: &mut Formatter| match &run.last {
+            Some(_) if run.step == Some(0) => {
+                // Repeated items are all in the caller's list, so their number fits in a `usize`.
+                formatter.write_repeat(f, &items.item(run.first.borrow()), run.len as usize)
+            }
+            Some(last) => {
+                // A half-open range cannot be written backwards.
+                let end = if run.descending || run.step.is_some() {
+                    None
+                } else {
+                    (items.steps.successor)(last.borrow())
+                };
+                let end = end.as_ref().map(|end| items.item(end));
+                let range = RunRange {
+                    first: &items.item(run.first.borrow()),
+                    last: &items.item(last.borrow()),
+                    end: end.as_ref().map(|end| end as &dyn Debug),
+                    len: run.len,
+                    descending: run.descending,
+                    step: run.step,
+                };
+                formatter.write_range(f, &range, options)?;
+                if let Some(len) = run_len(run.len).filter(|_| options.counts) {
+                    write!(f, " ({})", len)?;
+                }
+                if run.missing != 0 {
+                    fmt_missing(f, &run, options, items)?;
+                }
+                Ok(())
+            }
+            None => formatter.write_single(f, &items.item(run.first.borrow())),
+        })?;
+    }
+    list.end()
+}
+
+/// Writes the items that are missing from a run with gaps, in the style given by
+/// [`Options::gaps`], e.g. ` (missing 103, 107)` or ` (-2)`.
+fn fmt_missing<T, Q, R, W>(
+    f: &mut Formatter,
+    run: &Span<Q, R>,
+    options: &Options,
+    items: &ItemWriter<T, W>,
+) -> core::fmt::Result
+where
+    Q: Borrow<T>,
+    R: Iterator<Item = Q> + Clone,
+    W: Fn(&T, &mut Formatter) -> core::fmt::Result,
+{
+    let steps = &items.steps;
+    let step = if run.descending {
+        steps.predecessor
+    } else {
+        steps.successor
+    };
+
+    // The missing values can only be listed if the type can step from one item to the next.
+    if options.gaps == GapStyle::Count || step(run.first.borrow()).is_none() {
+        return write!(f, " (-{})", run.missing);
+    }
+
+    f.write_str(" (missing ")?;
+    let mut started = false;
+    let mut present = run.items.clone().take(run.len as usize);
+    let Some(mut prev) = present.next() else {
+        return f.write_str(")");
+    };
+    for next in present {
+        let (low, high) = if run.descending {
+            (next.borrow(), prev.borrow())
+        } else {
+            (prev.borrow(), next.borrow())
+        };
+        let gap = (steps.distance)(low, high).unwrap_or(1);
+        let mut missing = step(prev.borrow());
+        for _ in 1..gap {
+            let Some(item) = missing else {
+                break;
+            };
+            if core::mem::replace(&mut started, true) {
+                f.write_str(", ")?;
+            }
+            (items.write_item)(&item, f)?;
+            missing = step(&item);
+        }
+        prev = next;
+    }
+    f.write_str(")")
+}
+
+/// Writes the entries of a list, either on one line, or in the multi-line layout used for
+/// `{:#?}`. The separators and brackets are written by the [`RunFormatter`] in both layouts.
+struct ListWriter<'f, 'b> {
+    f: &'f mut Formatter<'b>,
+
+    /// Writes the separators and brackets
+    formatter: &'f dyn RunFormatter,
+
+    /// The multi-line layout, or `None` to write the list on one line
+    layout: Option<Pretty>,
+
+    /// `true` once an entry has been written
+    started: bool,
+
+    /// The number of characters written on the current line, for [`Pretty::Wrap`]
+    column: usize,
+
+    /// The caller's flags, which are used to measure entries for [`Pretty::Wrap`]
+    flags: Flags,
+}
+
+/// The indentation of each line in the multi-line layout, which matches `debug_list`.
+const INDENT: &str = "    ";
+
+impl<'f, 'b> ListWriter<'f, 'b> {
+    fn new(f: &'f mut Formatter<'b>, formatter: &'f dyn RunFormatter, pretty: Pretty) -> Self {
+        let layout = if f.alternate() && pretty != Pretty::Off {
+            Some(pretty)
+        } else {
+            None
+        };
+        let flags = Flags::of(f);
+        Self {
+            f,
+            formatter,
+            layout,
+            started: false,
+            column: 0,
+            flags,
+        }
+    }
+
+    fn multiline(&self) -> bool {
+        self.layout.is_some()
+    }
+
+    /// Returns `true` if the list would write anything when it has no entries.
+    fn has_brackets(&self) -> bool {
+        let multiline = self.multiline();
+        let brackets = |f: &mut Formatter| {
+            self.formatter.write_open(f, multiline)?;
+            self.formatter.write_close(f, multiline)
+        };
+        measure(self.flags, &FmtFn(brackets)) != 0
+    }
+
+    fn begin(&mut self) -> core::fmt::Result {
+        self.formatter.write_open(self.f, self.multiline())
+    }
+
+    fn entry(&mut self, entry: &dyn Fn(&mut Formatter) -> core::fmt::Result) -> core::fmt::Result {
+        let started = core::mem::replace(&mut self.started, true);
+
+        match self.layout {
+            None => {
+                if started {
+                    self.formatter.write_between(self.f, false)?;
+                }
+                entry(self.f)
+            }
+            Some(Pretty::Wrap(width)) => {
+                let formatter = self.formatter;
+                let between = |f: &mut Formatter| formatter.write_between(f, true);
+                let len = measure(self.flags, &FmtFn(entry)) + measure(self.flags, &FmtFn(between));
+                if started && self.column + 1 + len <= width {
+                    self.f.write_str(" ")?;
+                    self.column += 1;
+                } else {
+                    self.f.write_str("\n")?;
+                    self.f.write_str(INDENT)?;
+                    self.column = INDENT.len();
+                }
+                self.indented(entry)?;
+                self.column += len;
+                between(self.f)
+            }
+            Some(_) => {
+                self.f.write_str("\n")?;
+                self.f.write_str(INDENT)?;
+                self.indented(entry)?;
+                self.formatter.write_between(self.f, true)
+            }
+        }
+    }
+
+    /// Writes an entry of the multi-line layout, indenting the lines of items that are written
+    /// on more than one line so that they nest inside the list.
+    fn indented(
+        &mut self,
+        entry: &dyn Fn(&mut Formatter) -> core::fmt::Result,
+    ) -> core::fmt::Result {
+        let mut out = PadAdapter {
+            out: &mut *self.f,
+            on_newline: false,
+        };
+        self.flags.write(&mut out, &FmtFn(entry))
+    }
+
+    fn end(&mut self) -> core::fmt::Result {
+        if self.multiline() && self.started {
+            self.f.write_str("\n")?;
+        }
+        self.formatter.write_close(self.f, self.multiline())
+    }
+}
+
+/// Indents each line written through it after the first, like the adapter that `debug_list` uses
+/// for `{:#?}`.
+struct PadAdapter<'w> {
+    out: &'w mut dyn Write,
+
+    /// `true` if the last character written was a line break
+    on_newline: bool,
+}
+
+impl<'w> Write for PadAdapter<'w> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        for line in s.split_inclusive('\n') {
+            if self.on_newline {
+                self.out.write_str(INDENT)?;
+            }
+            self.on_newline = line.ends_with('\n');
+            self.out.write_str(line)?;
+        }
+        Ok(())
+    }
+}
+
+fn plural(n: u128) -> &'static str {
+    if n == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+/// Adapts a closure to `Debug`, so that it can be written into sinks other than the caller's
+/// `Formatter`.
+struct FmtFn<F>(F);
+
+impl<F: Fn(&mut Formatter) -> core::fmt::Result> Debug for FmtFn<F> {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        (self.0)(f)
+    }
+}
+
+/// Returns the number of characters in the output of `value`, written with `flags`, without
+/// allocating.
+fn measure(flags: Flags, value: &impl Debug) -> usize {
+    struct Counter(usize);
+
+    impl Write for Counter {
+        fn write_str(&mut self, s: &str) -> core::fmt::Result {
+            self.0 += s.chars().count();
+            Ok(())
+        }
+    }
+
+    let mut counter = Counter(0);
+    _ = flags.write(&mut counter, value);
+    counter.0
+}
+
+/// The flags of a `Formatter` that change how items are written, other than the width, fill and
+/// precision. They are passed on to the new formatters that lists are measured and padded with.
+#[derive(Copy, Clone)]
+struct Flags {
+    /// `{:+?}`
+    sign_plus: bool,
+
+    /// `{:#?}`
+    alternate: bool,
+
+    /// `{:x?}` or `{:X?}`
+    hex: DebugHex,
+}
+
+/// The hexadecimal flag of a `Formatter`, which integers use in their `Debug` output.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum DebugHex {
+    Off,
+    Lower,
+    Upper,
+}
+
+impl Flags {
+    fn of(f: &Formatter) -> Self {
+        Self {
+            sign_plus: f.sign_plus(),
+            alternate: f.alternate(),
+            hex: DebugHex::of(f),
+        }
+    }
+
+    /// Writes `value` into `out` with these flags.
+    fn write(self, out: &mut dyn Write, value: &dyn Debug) -> core::fmt::Result {
+        match (self.hex, self.sign_plus, self.alternate) {
+            (DebugHex::Off, false, false) => write!(out, "{:?}", value),
+            (DebugHex::Off, false, true) => write!(out, "{:#?}", value),
+            (DebugHex::Off, true, false) => write!(out, "{:+?}", value),
+            (DebugHex::Off, true, true) => write!(out, "{:+#?}", value),
+            (DebugHex::Lower, false, false) => write!(out, "{:x?}", value),
+            (DebugHex::Lower, false, true) => write!(out, "{:#x?}", value),
+            (DebugHex::Lower, true, false) => write!(out, "{:+x?}", value),
+            (DebugHex::Lower, true, true) => write!(out, "{:+#x?}", value),
+            (DebugHex::Upper, false, false) => write!(out, "{:X?}", value),
+            (DebugHex::Upper, false, true) => write!(out, "{:#X?}", value),
+            (DebugHex::Upper, true, false) => write!(out, "{:+X?}", value),
+            (DebugHex::Upper, true, true) => write!(out, "{:+#X?}", value),
+        }
+    }
+}
+
+impl DebugHex {
+    /// Reads the hexadecimal flag of `f`.
+    ///
+    /// Stable Rust has no accessor for this flag, so it is read from `Formatter::flags`, which is
+    /// deprecated and whose bits are not documented. The `{:x?}` and `{:X?}` flags have been bits
+    /// 4 and 5 since they were added in Rust 1.26, and `test_flags_round_trip` checks that they
+    /// still are. This is the only place that reads them.
+    #[allow(deprecated)]
+    fn of(f: &Formatter) -> Self {
+        const DEBUG_LOWER_HEX: u32 = 1 << 4;
+        const DEBUG_UPPER_HEX: u32 = 1 << 5;
+
+        let flags = f.flags();
+        if flags & DEBUG_LOWER_HEX != 0 {
+            Self::Lower
+        } else if flags & DEBUG_UPPER_HEX != 0 {
+            Self::Upper
+        } else {
+            Self::Off
+        }
+    }
+}
+
+/// Writes a range of two or more items in the notation given by `options`. This is the default
+/// implementation of [`RunFormatter::write_range`].
+pub(crate) fn fmt_range(
+    f: &mut Formatter,
+    range: &RunRange,
+    options: &Options,
+) -> core::fmt::Result {
+    match options.notation {
+        RangeNotation::Dash => fmt_dash_range(f, range, options),
+        RangeNotation::Inclusive => fmt_inclusive(f, range),
+        RangeNotation::HalfOpen => match range.end {
+            Some(end) => {
+                range.first.fmt(f)?;
+                f.write_str("..")?;
+                end.fmt(f)
+            }
+            None => fmt_inclusive(f, range),
+        },
+        RangeNotation::Interval => {
+            f.write_str("[")?;
+            range.first.fmt(f)?;
+            f.write_str(", ")?;
+            range.last.fmt(f)?;
+            f.write_str("]")?;
+            fmt_step(f, range, "/")
+        }
+        RangeNotation::Colon => {
+            range.first.fmt(f)?;
+            f.write_str(":")?;
+            range.last.fmt(f)?;
+            fmt_step(f, range, "/")
+        }
+    }
+}
+
+/// Writes `first` on its own, or the range from `first` to `last` in the notation given by
+/// `options`, with `prefix` before each value. This writes one coordinate of an extent or the keys
+/// of a map entry, which are not the items of the run that contains them.
+pub(crate) fn fmt_prefixed_range<T: Debug + IsAdjacent>(
+    f: &mut Formatter,
+    prefix: &str,
+    first: &T,
+    last: Option<&T>,
+    len: u128,
+    options: &Options,
+) -> core::fmt::Result {
+    let Some(last) = last else {
+        return prefixed(prefix, first, false).fmt(f);
+    };
+
+    // The prefix hides the sign of the values from `fmt_range`, so the negative style is applied
+    // here, to the values themselves.
+    let mut options = *options;
+    let mut parenthesize = false;
+    if options.notation == RangeNotation::Dash && options.sep.contains('-') {
+        match options.negatives {
+            NegativeStyle::Auto if is_negative(first) || is_negative(last) => {
+                options.notation = RangeNotation::Inclusive;
+            }
+            NegativeStyle::Parenthesize => parenthesize = true,
+            _ => {}
+        }
+        options.negatives = NegativeStyle::Keep;
+    }
+
+    let end = last.successor();
+    let end = end.as_ref().map(|end| prefixed(prefix, end, parenthesize));
+    let range = RunRange {
+        first: &prefixed(prefix, first, parenthesize),
+        last: &prefixed(prefix, last, parenthesize),
+        end: end.as_ref().map(|end| end as &dyn Debug),
+        len,
+        descending: false,
+        step: None,
+    };
+    fmt_range(f, &range, &options)
+}
+
+/// Writes `value` with the caller's flags other than `{:#?}`, which would spread a value such as
+/// an enum variant over several lines. This writes the values of map entries, which follow their
+/// keys on the same line.
+pub(crate) fn fmt_inline(f: &mut Formatter, value: &dyn Debug) -> core::fmt::Result {
+    let flags = Flags {
+        alternate: false,
+        ..Flags::of(f)
+    };
+    flags.write(f, value)
+}
+
+/// Returns a value whose `Debug` implementation writes `prefix` and then `value`, wrapped in
+/// parentheses if `parenthesize` is `true` and `value` is negative.
+fn prefixed<'v, T: Debug>(
+    prefix: &'v str,
+    value: &'v T,
+    parenthesize: bool,
+) -> FmtFn<impl Fn(&mut Formatter) -> core::fmt::Result + 'v> {
+    FmtFn(move |f: &mut Formatter| {
+        let parenthesize = parenthesize && is_negative(value);
+        if parenthesize {
+            f.write_str("(")?;
+        }
+        f.write_str(prefix)?;
+        value.fmt(f)?;
+        if parenthesize {
+            f.write_str(")")?;
+        }
+        Ok(())
+    })
+}
+
+/// Writes a range as a Rust inclusive range, e.g. `12..=15` or `0..=16 step 4`.
+fn fmt_inclusive(f: &mut Formatter, range: &RunRange) -> core::fmt::Result {
+    range.first.fmt(f)?;
+    f.write_str("..=")?;
+    range.last.fmt(f)?;
+    fmt_step(f, range, " step ")
+}
+
+/// Writes the step of a strided range after `prefix`, using the caller's formatting flags. Writes
+/// nothing if the range is not strided.
+fn fmt_step(f: &mut Formatter, range: &RunRange, prefix: &str) -> core::fmt::Result {
+    match range.step {
+        Some(step) => {
+            f.write_str(prefix)?;
+            Debug::fmt(&step, f)
+        }
+        None => Ok(()),
+    }
+}
+
+/// Writes a range using `options.sep`, unless an endpoint is negative and the separator would
+/// make the output ambiguous.
+fn fmt_dash_range(f: &mut Formatter, range: &RunRange, options: &Options) -> core::fmt::Result {
+    let style = if options.sep.contains('-') {
+        options.negatives
+    } else {
+        NegativeStyle::Keep
+    };
+
+    match style {
+        NegativeStyle::Auto if range.is_negative() => fmt_inclusive(f, range),
+        NegativeStyle::Parenthesize => {
+            fmt_parenthesized(f, range.first)?;
+            f.write_str(options.sep)?;
+            fmt_parenthesized(f, range.last)?;
+            fmt_step(f, range, "/")
+        }
+        _ => {
+            range.first.fmt(f)?;
+            f.write_str(options.sep)?;
+            range.last.fmt(f)?;
+            fmt_step(f, range, "/")
+        }
+    }
+}
+
+/// Writes `item`, wrapped in parentheses if it is negative.
+fn fmt_parenthesized(f: &mut Formatter, item: &dyn Debug) -> core::fmt::Result {
+    if is_negative(item) {
+        f.write_str("(")?;
+        item.fmt(f)?;
+        f.write_str(")")
+    } else {
+        item.fmt(f)
+    }
+}
+
+#[test]
+fn test_flags_round_trip() {
+    use std::string::String;
+
+    /// Writes `[-5, 10]` through the flags that it reads from its formatter.
+    struct Probe;
+
+    impl Debug for Probe {
+        fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+            let mut out = String::new();
+            Flags::of(f).write(&mut out, &[-5i32, 10])?;
+            f.write_str(&out)
+        }
+    }
+
+    let value = [-5i32, 10];
+    assert_eq!(format!("{:?}", Probe), format!("{:?}", value));
+    assert_eq!(format!("{:#?}", Probe), format!("{:#?}", value));
+    assert_eq!(format!("{:+?}", Probe), format!("{:+?}", value));
+    assert_eq!(format!("{:+#?}", Probe), format!("{:+#?}", value));
+    assert_eq!(format!("{:x?}", Probe), format!("{:x?}", value));
+    assert_eq!(format!("{:#x?}", Probe), format!("{:#x?}", value));
+    assert_eq!(format!("{:+x?}", Probe), format!("{:+x?}", value));
+    assert_eq!(format!("{:+#x?}", Probe), format!("{:+#x?}", value));
+    assert_eq!(format!("{:X?}", Probe), format!("{:X?}", value));
+    assert_eq!(format!("{:+#X?}", Probe), format!("{:+#X?}", value));
+    assert_eq!(
+        format!("{:+#x?}", Probe),
+        "[\n    +0xfffffffb,\n    +0xa,\n]"
+    );
+}